@@ -0,0 +1,65 @@
+// CRT look for the main camera, see `src/crt.rs`.
+
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+
+@group(0) @binding(0) var screen_texture: texture_2d<f32>;
+@group(0) @binding(1) var texture_sampler: sampler;
+
+struct Crt {
+    // The camera's viewport, relative to the whole texture.
+    viewport_origin: vec2<f32>,
+    viewport_size: vec2<f32>,
+    scanline_strength: f32,
+    curvature: f32,
+    overlay_strength: f32,
+    scanlines: f32,
+}
+@group(0) @binding(2) var<uniform> crt: Crt;
+
+const PI: f32 = 3.14159265;
+
+// The cellophane strips of the original cabinet, as fractions of the
+// viewport's height from the top: red over the top, green over the player.
+const RED_STRIP_END: f32 = 0.15;
+const GREEN_STRIP_START: f32 = 0.72;
+const RED: vec3<f32> = vec3<f32>(1.0, 0.25, 0.25);
+const GREEN: vec3<f32> = vec3<f32>(0.25, 1.0, 0.35);
+
+@fragment
+fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    // Position in the viewport from 0 to 1, the letterbox bars stay as they are.
+    let screen = (in.uv - crt.viewport_origin) / crt.viewport_size;
+    if any(screen < vec2<f32>(0.0)) || any(screen > vec2<f32>(1.0)) {
+        return textureSampleLevel(screen_texture, texture_sampler, in.uv, 0.0);
+    }
+
+    // Barrel distortion, bulging the middle of the picture towards the viewer.
+    let centered = screen * 2.0 - 1.0;
+    let curved = (centered * (1.0 + crt.curvature * dot(centered, centered))) * 0.5 + 0.5;
+    if any(curved < vec2<f32>(0.0)) || any(curved > vec2<f32>(1.0)) {
+        return vec4<f32>(0.0, 0.0, 0.0, 1.0);
+    }
+    var color = textureSampleLevel(
+        screen_texture,
+        texture_sampler,
+        crt.viewport_origin + curved * crt.viewport_size,
+        0.0
+    ).rgb;
+
+    var tint = vec3<f32>(1.0);
+    if curved.y < RED_STRIP_END {
+        tint = RED;
+    } else if curved.y > GREEN_STRIP_START {
+        tint = GREEN;
+    }
+    color *= mix(vec3<f32>(1.0), tint, crt.overlay_strength);
+
+    // Dark gaps between the lines, brightest in the middle of a line.
+    let scanline = 0.5 + 0.5 * cos(2.0 * PI * curved.y * crt.scanlines);
+    color *= 1.0 - crt.scanline_strength * scanline;
+
+    // Darker corners, like the edges of the tube.
+    color *= 1.0 - 0.25 * dot(centered, centered) * 0.5;
+
+    return vec4<f32>(color, 1.0);
+}
@@ -0,0 +1,49 @@
+//! Compares the speed of the spatial grid broad phase to testing every pair
+//! of colliders. That both find the same collisions is tested in
+//! `spatial_grid.rs`.
+//!
+//! Run with `cargo bench --bench collision`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use space_invaders_demo::collision::find_collisions_brute_force;
+use space_invaders_demo::spatial_grid::{random_bodies, SpatialGrid};
+
+const ITERATIONS: u32 = 20;
+
+fn measure(mut run: impl FnMut()) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        run();
+    }
+    start.elapsed() / ITERATIONS
+}
+
+fn main() {
+    let mut rng = StdRng::seed_from_u64(0x5ace);
+    let mut grid = SpatialGrid::new(16.0);
+    let mut brute_force_pairs = Vec::new();
+    let mut grid_pairs = Vec::new();
+
+    println!(
+        "{:>8} {:>8} {:>14} {:>14}",
+        "bodies", "pairs", "brute force", "grid"
+    );
+    for count in [100, 500, 1_000, 2_000, 5_000, 10_000] {
+        let bodies = random_bodies(count, &mut rng);
+
+        let brute_force =
+            measure(|| find_collisions_brute_force(black_box(&bodies), &mut brute_force_pairs));
+        let grid_time = measure(|| grid.find_collisions(black_box(&bodies), &mut grid_pairs));
+        println!(
+            "{:>8} {:>8} {:>14?} {:>14?}",
+            count,
+            grid_pairs.len(),
+            brute_force,
+            grid_time
+        );
+    }
+}
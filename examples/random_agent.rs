@@ -0,0 +1,44 @@
+//! Plays a few games with random actions in the headless environment and
+//! reports the rewards and how fast the game ran.
+//!
+//! Run with `cargo run --release --example random_agent`.
+
+use std::time::Instant;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use space_invaders_demo::env::{Action, EnvConfig, Environment};
+
+const EPISODES: u64 = 5;
+
+fn main() {
+    let mut env = Environment::new(EnvConfig::default()).expect("the data files should load");
+    let mut rng = StdRng::seed_from_u64(0);
+    let started = Instant::now();
+    let mut total_steps = 0u64;
+    for episode in 0..EPISODES {
+        env.reset(episode);
+        let mut total_reward = 0.0;
+        let mut steps = 0u64;
+        loop {
+            let action = Action {
+                movement: rng.gen_range(-1.0..=1.0),
+                fire: rng.gen_bool(0.5),
+            };
+            let (_observation, reward, done) = env.step(action);
+            total_reward += reward;
+            steps += 1;
+            if done {
+                break;
+            }
+        }
+        total_steps += steps;
+        println!("episode {episode}: reward {total_reward} in {steps} steps");
+    }
+    let seconds = started.elapsed().as_secs_f64();
+    println!(
+        "{:.0} steps per second, {:.1} times faster than real time",
+        total_steps as f64 / seconds,
+        total_steps as f64 * EnvConfig::default().frames_per_step as f64 / 60.0 / seconds
+    );
+}
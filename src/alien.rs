@@ -1,73 +1,193 @@
+use std::time::Duration;
+
 use bevy::prelude::*;
+use rand::seq::IteratorRandom;
+use rand::Rng;
 
-use crate::game::OnGameScreen;
+use crate::alien_kind::{AlienKinds, FirePattern};
+use crate::animation::{DespawnWhenFinished, SpriteAnimations};
+use crate::asset_errors::{AssetErrors, TrackAssetErrors};
+use crate::bullet::{AlienBullet, Velocity, BULLET_HEIGHT};
+use crate::collision::{layer, Collider, CollisionLayers};
+use crate::config::GameConfig;
+use crate::difficulty::{Difficulty, DifficultySettings};
+use crate::game::{GameRng, OnGameScreen};
+use crate::level::Level;
 use crate::load_assets::Assets;
+use crate::particles::{ParticleEffect, ParticleEmitter};
+use crate::player::Player;
 use crate::{GameStates, WORLD_HEIGHT, WORLD_WIDTH};
 
 pub const ALIEN_HEIGHT: f32 = 9f32;
-const ALIEN_WIDTH: f32 = 9f32;
-const ALIEN_GAP: f32 = 5f32;
-const ALIEN_DOWN_TRAVEL_DISTANCE: f32 = ALIEN_HEIGHT + ALIEN_GAP;
-const ALIEN_HORIZONTAL_TRAVEL_DISTANCE: f32 = WORLD_WIDTH - 8.0 * ALIEN_WIDTH - 7.0 * ALIEN_GAP;
-const ALIEN_SPEED: f32 = 96f32;
-
-const TIME_TRAVELING_HORIZONTALLY: f32 = ALIEN_HORIZONTAL_TRAVEL_DISTANCE / ALIEN_SPEED;
+pub const ALIEN_WIDTH: f32 = 9f32;
 
-const TIME_TRAVELING_VERTICALLY: f32 = ALIEN_DOWN_TRAVEL_DISTANCE / ALIEN_SPEED;
+const ALIEN_BULLET_COLOR: Color = Color::srgb(1.0, 0.4, 0.4);
+const ALIEN_BULLET_TRAIL: ParticleEffect = ParticleEffect {
+    start_color: Color::srgba(1.0, 0.4, 0.4, 0.6),
+    end_color: Color::srgba(0.6, 0.1, 0.1, 0.0),
+    ..ParticleEffect::TRAIL
+};
+/// Angle between the bullets of the spread fire pattern, in degrees.
+const SPREAD_ANGLE: f32 = 20.0;
+/// Aimed shots are steered at most this far away from straight down.
+const MAX_AIM_ANGLE: f32 = 45.0;
+/// Distance the formation travels per march step. The aliens' animation
+/// moves on by one frame per step.
+const MARCH_STEP_DISTANCE: f32 = 6.0;
 
-// To really do different levels with differently sized aliens and
-// different reactions, we should probably invent some kind
-// of DSL or even use a scripting language like lua/rhai/whatever
-// instead of hardcoding paths and alien properties here.
+// The aliens of a row share an `AlienKind`, which decides their sprite,
+// size, hit points, points and how they shoot. The formation is laid out
+// on a grid of ALIEN_WIDTH x ALIEN_HEIGHT cells, every kind fits into one.
 //
-// We also would need a hitpoint system of some kind.
-//
-// A row of aliens will look like that
+// A row of aliens will look like that (for a level with 8 columns)
 // 1 2 3 4 5 6 7 8
 // XGXGXGXGXGXGXGX
 // with X being an alien and G being the space between aliens, the gap.
 // so there are 8 aliens in a row and 7 gaps.
 //
 // The Movement curve will have to travel WORLD_WIDTH-8*alien_width-7*gap_width
-// horizontally.
+// horizontally, see `Level::formation_width`.
 
 pub struct AlienPlugin;
 
 #[derive(Component)]
 pub struct Alien {
+    row: u32,
+    col: u32,
     initial_offset: Vec2,
     setup_time: f32,
+    hit_points: u32,
+    points: u32,
+    fire_pattern: FirePattern,
+    damaged_sheet: Option<Handle<Image>>,
+}
+
+impl Alien {
+    /// Takes one hit point and shows the damage. Returns the points for
+    /// shooting it down, if that was the last one.
+    pub fn take_hit(&mut self, sprite: &mut Sprite) -> Option<u32> {
+        self.hit_points = self.hit_points.saturating_sub(1);
+        if self.hit_points == 0 {
+            return Some(self.points);
+        }
+        if let Some(damaged_sheet) = &self.damaged_sheet {
+            sprite.image = damaged_sheet.clone();
+        }
+        None
+    }
+}
+
+/// The path all aliens of the formation follow, offset by their place in it.
+#[derive(Resource)]
+pub struct AlienMovementCurve(AnimatableKeyframeCurve<Vec2>);
+
+impl AlienMovementCurve {
+    /// Where `alien` is at `formation_time`, see `FormationClock::elapsed`.
+    pub fn position(&self, alien: &Alien, formation_time: f32) -> Vec2 {
+        let t = (formation_time - alien.setup_time).max(0f32);
+        alien.initial_offset + self.0.sample_clamped(t)
+    }
+}
+
+#[derive(Resource)]
+struct AlienFireTimer(Timer);
+
+/// Sent when an alien got shot down.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct AlienKilled {
+    pub position: Vec2,
 }
 
+/// The time the aliens live in. It runs slower than the real time while
+/// the slow time power-up is active.
 #[derive(Resource)]
-struct AlienMovementCurve(AnimatableKeyframeCurve<Vec2>);
+pub struct FormationClock {
+    elapsed: f32,
+    /// Factor of the real time passing for the aliens.
+    pub speed: f32,
+    /// Formation time of one march step, shorter for faster aliens.
+    step_duration: f32,
+}
+
+impl Default for FormationClock {
+    fn default() -> Self {
+        FormationClock {
+            elapsed: 0.0,
+            speed: 1.0,
+            step_duration: march_step_duration(
+                &GameConfig::default(),
+                &Difficulty::default().settings(),
+            ),
+        }
+    }
+}
+
+impl FormationClock {
+    /// Formation time passed since the wave started.
+    pub fn elapsed(&self) -> f32 {
+        self.elapsed
+    }
+
+    /// Number of march steps the formation took since the wave started.
+    pub fn march_step(&self) -> u64 {
+        (self.elapsed / self.step_duration) as u64
+    }
+}
+
+fn march_step_duration(config: &GameConfig, difficulty: &DifficultySettings) -> f32 {
+    MARCH_STEP_DISTANCE / (config.alien_speed * difficulty.alien_speed)
+}
 
 impl Plugin for AlienPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, setup_alien_movement_curve);
+        app.init_resource::<FormationClock>();
+        app.add_event::<AlienKilled>();
         app.add_systems(OnEnter(GameStates::Game), setup_aliens);
-        app.add_systems(Update, move_aliens.run_if(in_state(GameStates::Game)));
+        app.add_systems(
+            Update,
+            (alien_fire, spawn_explosions).run_if(in_state(GameStates::Game)),
+        );
+        app.add_systems(
+            Update,
+            (
+                advance_formation_clock,
+                reapply_level.after(TrackAssetErrors),
+                reapply_config,
+                move_aliens,
+            )
+                .chain()
+                .run_if(in_state(GameStates::Game)),
+        );
     }
 }
 
-fn setup_alien_movement_curve(mut commands: Commands) {
+fn alien_movement_curve(
+    level: &Level,
+    config: &GameConfig,
+    difficulty: &DifficultySettings,
+) -> AnimatableKeyframeCurve<Vec2> {
+    let alien_speed = config.alien_speed * difficulty.alien_speed;
+    let down_travel_distance = ALIEN_HEIGHT + config.alien_gap;
+    let horizontal_travel_distance = WORLD_WIDTH - level.formation_width(config);
+    let time_traveling_horizontally = horizontal_travel_distance / alien_speed;
+    let time_traveling_vertically = down_travel_distance / alien_speed;
     let y_pos_iter = (0..)
         .flat_map(|v| {
             [
-                -(v as f32) * ALIEN_DOWN_TRAVEL_DISTANCE,
-                -(v as f32) * ALIEN_DOWN_TRAVEL_DISTANCE,
+                -(v as f32) * down_travel_distance,
+                -(v as f32) * down_travel_distance,
             ]
         })
         .take_while(|v| *v > -WORLD_HEIGHT * 1.5f32);
     let x_pos_iter = [
         0f32,
-        ALIEN_HORIZONTAL_TRAVEL_DISTANCE,
-        ALIEN_HORIZONTAL_TRAVEL_DISTANCE,
+        horizontal_travel_distance,
+        horizontal_travel_distance,
         0f32,
     ]
     .into_iter()
     .cycle();
-    let t_pos_iter = [TIME_TRAVELING_HORIZONTALLY, TIME_TRAVELING_VERTICALLY]
+    let t_pos_iter = [time_traveling_horizontally, time_traveling_vertically]
         .into_iter()
         .cycle()
         .scan(0.0f32, |state, increment| {
@@ -75,45 +195,309 @@ fn setup_alien_movement_curve(mut commands: Commands) {
             *state += increment;
             Some(result)
         });
-    let alien_movement_curve = AnimatableKeyframeCurve::new(
+    AnimatableKeyframeCurve::new(
         t_pos_iter.zip(x_pos_iter.zip(y_pos_iter).map(|(x, y)| Vec2::new(x, y))),
     )
-    .unwrap();
-    commands.insert_resource(AlienMovementCurve(alien_movement_curve));
-}
-
-fn setup_aliens(mut commands: Commands, assets: Res<Assets>, time: Res<Time>) {
-    let alien_texture = &assets.alien;
-    for row in 0..3 {
-        let row = row as f32;
-        for col in 0..8 {
-            let col = col as f32;
-            let x_off = -WORLD_WIDTH / 2.0 + col * (ALIEN_WIDTH + ALIEN_GAP);
-            let y_off = WORLD_HEIGHT / 2.0 - row * (ALIEN_HEIGHT + ALIEN_GAP);
+    .unwrap()
+}
+
+fn initial_offset(
+    row: u32,
+    col: u32,
+    config: &GameConfig,
+    difficulty: &DifficultySettings,
+) -> Vec2 {
+    Vec2::new(
+        -WORLD_WIDTH / 2.0 + col as f32 * (ALIEN_WIDTH + config.alien_gap),
+        WORLD_HEIGHT / 2.0 * difficulty.formation_height
+            - row as f32 * (ALIEN_HEIGHT + config.alien_gap),
+    )
+}
+
+fn setup_aliens(
+    mut commands: Commands,
+    assets: Res<Assets>,
+    mut animations: SpriteAnimations,
+    levels: Res<bevy::prelude::Assets<Level>>,
+    alien_kinds: Res<bevy::prelude::Assets<AlienKinds>>,
+    config: Res<GameConfig>,
+    difficulty: Res<Difficulty>,
+) {
+    let difficulty = difficulty.settings();
+    commands.insert_resource(FormationClock {
+        step_duration: march_step_duration(&config, &difficulty),
+        ..default()
+    });
+    // Fall back to the built in layout, if the level file is broken.
+    // The error is shown by the `AssetErrorsPlugin`.
+    let level = levels
+        .get(&assets.level)
+        .filter(|level| level.check_fits(&config).is_ok())
+        .cloned()
+        .unwrap_or_default();
+    let kinds = alien_kinds
+        .get(&assets.alien_kinds)
+        .cloned()
+        .unwrap_or_default();
+    spawn_formation(
+        &mut commands,
+        &mut animations,
+        &level,
+        &kinds,
+        &config,
+        &difficulty,
+        0.0,
+    );
+    commands.insert_resource(AlienFireTimer(Timer::from_seconds(
+        config.alien_fire_interval / difficulty.alien_fire_rate,
+        TimerMode::Repeating,
+    )));
+}
+
+fn spawn_formation(
+    commands: &mut Commands,
+    animations: &mut SpriteAnimations,
+    level: &Level,
+    kinds: &AlienKinds,
+    config: &GameConfig,
+    difficulty: &DifficultySettings,
+    setup_time: f32,
+) {
+    commands.insert_resource(AlienMovementCurve(alien_movement_curve(
+        level, config, difficulty,
+    )));
+    for (row, kind_name) in (0..).zip(&level.rows) {
+        let kind = kinds.get(kind_name);
+        for col in 0..level.columns {
+            let (sprite, animation) = animations.start(&kind.animation);
+            let initial_offset = initial_offset(row, col, config, difficulty);
             commands.spawn((
                 Alien {
-                    initial_offset: Vec2::new(x_off, y_off),
-                    setup_time: time.elapsed_secs(),
+                    row,
+                    col,
+                    initial_offset,
+                    setup_time,
+                    hit_points: kind.hit_points,
+                    points: kind.points,
+                    fire_pattern: kind.fire_pattern,
+                    damaged_sheet: kind
+                        .damaged_sheet
+                        .as_ref()
+                        .map(|path| animations.load_sheet(path)),
                 },
                 OnGameScreen,
+                Collider::aabb(
+                    kind.size(),
+                    CollisionLayers::new(layer::ALIEN, layer::PLAYER_BULLET),
+                ),
                 Sprite {
-                    image: alien_texture.clone(),
-                    ..Default::default()
+                    color: kind.color(),
+                    custom_size: Some(kind.size()),
+                    ..sprite
                 },
-                Transform::from_xyz(x_off, y_off, 20.0),
+                animation,
+                Transform::from_translation(initial_offset.extend(20.0)),
             ));
         }
     }
 }
 
-fn move_aliens(
+/// Applies an edited level or alien kinds file to the running wave. The
+/// formation is rebuilt from the new layout but keeps its position on the
+/// movement curve.
+#[allow(clippy::too_many_arguments)]
+fn reapply_level(
+    mut level_events: EventReader<AssetEvent<Level>>,
+    mut kind_events: EventReader<AssetEvent<AlienKinds>>,
+    mut commands: Commands,
+    assets: Res<Assets>,
+    levels: Res<bevy::prelude::Assets<Level>>,
+    alien_kinds: Res<bevy::prelude::Assets<AlienKinds>>,
+    config: Res<GameConfig>,
+    difficulty: Res<Difficulty>,
+    asset_server: Res<AssetServer>,
+    mut animations: SpriteAnimations,
+    mut asset_errors: ResMut<AssetErrors>,
+    aliens: Query<(Entity, &Alien)>,
+    clock: Res<FormationClock>,
+) {
+    let level_modified = level_events
+        .read()
+        .any(|event| event.is_modified(&assets.level));
+    let kinds_modified = kind_events
+        .read()
+        .any(|event| event.is_modified(&assets.alien_kinds));
+    if !level_modified && !kinds_modified {
+        return;
+    }
+    let Some(level) = levels.get(&assets.level) else {
+        return;
+    };
+    let kinds = alien_kinds
+        .get(&assets.alien_kinds)
+        .cloned()
+        .unwrap_or_default();
+    if let Err(error) = level
+        .check_fits(&config)
+        .and_then(|()| level.check_kinds(&kinds))
+    {
+        let path = asset_server
+            .get_path(&assets.level)
+            .map(|path| path.to_string())
+            .unwrap_or_default();
+        asset_errors.report(path, format!("invalid values: {error}"));
+        return;
+    }
+    let setup_time = aliens
+        .iter()
+        .map(|(_, alien)| alien.setup_time)
+        .next()
+        .unwrap_or(clock.elapsed);
+    for (entity, _) in &aliens {
+        commands.entity(entity).despawn();
+    }
+    spawn_formation(
+        &mut commands,
+        &mut animations,
+        level,
+        &kinds,
+        &config,
+        &difficulty.settings(),
+        setup_time,
+    );
+}
+
+/// Applies changed tuning values to the running wave, keeping the aliens
+/// which are still alive.
+#[allow(clippy::too_many_arguments)]
+fn reapply_config(
+    config: Res<GameConfig>,
+    difficulty: Res<Difficulty>,
+    mut commands: Commands,
+    assets: Res<Assets>,
+    levels: Res<bevy::prelude::Assets<Level>>,
+    mut aliens: Query<&mut Alien>,
+    mut fire_timer: ResMut<AlienFireTimer>,
+    mut clock: ResMut<FormationClock>,
+) {
+    if !config.is_changed() || config.is_added() {
+        return;
+    }
+    let difficulty = difficulty.settings();
+    let level = levels.get(&assets.level).cloned().unwrap_or_default();
+    commands.insert_resource(AlienMovementCurve(alien_movement_curve(
+        &level,
+        &config,
+        &difficulty,
+    )));
+    for mut alien in &mut aliens {
+        alien.initial_offset = initial_offset(alien.row, alien.col, &config, &difficulty);
+    }
+    fire_timer.0.set_duration(Duration::from_secs_f32(
+        config.alien_fire_interval / difficulty.alien_fire_rate,
+    ));
+    clock.step_duration = march_step_duration(&config, &difficulty);
+}
+
+/// Every now and then a random alien shoots back, in the way of its kind.
+#[allow(clippy::too_many_arguments)]
+fn alien_fire(
     time: Res<Time>,
+    clock: Res<FormationClock>,
+    config: Res<GameConfig>,
+    mut fire_timer: ResMut<AlienFireTimer>,
+    aliens: Query<(&Alien, &Transform)>,
+    player: Option<Single<&Transform, With<Player>>>,
+    assets: Res<Assets>,
+    mut rng: ResMut<GameRng>,
+    mut commands: Commands,
+) {
+    if !fire_timer
+        .0
+        .tick(time.delta().mul_f32(clock.speed))
+        .just_finished()
+    {
+        return;
+    }
+    let rng = &mut rng.0;
+    let Some((shooter, transform)) = aliens
+        .iter()
+        .filter(|(alien, _)| alien.fire_pattern != FirePattern::Never)
+        .choose(rng)
+    else {
+        return;
+    };
+    let muzzle = Vec2::new(
+        transform.translation.x,
+        transform.translation.y - ALIEN_HEIGHT / 2.0,
+    );
+    let angles = match shooter.fire_pattern {
+        FirePattern::Never => vec![],
+        FirePattern::Straight => vec![0.0],
+        FirePattern::Aimed => {
+            let aim = player.map_or(0.0, |player| {
+                let to_player = player.translation.truncate() - muzzle;
+                to_player.x.atan2(-to_player.y).to_degrees()
+            });
+            // A little jitter, so standing still is not a death sentence.
+            vec![(aim + rng.gen_range(-5.0..5.0)).clamp(-MAX_AIM_ANGLE, MAX_AIM_ANGLE)]
+        }
+        FirePattern::Spread => vec![-SPREAD_ANGLE, 0.0, SPREAD_ANGLE],
+    };
+    for angle in angles {
+        // Angles turn away from straight down, positive towards +x.
+        let rotation = Quat::from_rotation_z(angle.to_radians());
+        let direction = Vec2::new(angle.to_radians().sin(), -angle.to_radians().cos());
+        commands.spawn((
+            AlienBullet,
+            ParticleEmitter::new(ALIEN_BULLET_TRAIL),
+            Velocity(direction * config.alien_bullet_speed),
+            OnGameScreen,
+            Transform::from_translation(muzzle.extend(20.0)).with_rotation(rotation),
+            // A bit wider than the sprite, so grazing the player counts.
+            Collider::circle(
+                BULLET_HEIGHT / 2.0,
+                CollisionLayers::new(layer::ALIEN_BULLET, layer::PLAYER),
+            ),
+            Sprite {
+                image: assets.bullet.clone(),
+                color: ALIEN_BULLET_COLOR,
+                flip_y: true,
+                ..Default::default()
+            },
+        ));
+    }
+}
+
+/// Leaves an explosion where an alien got shot down.
+fn spawn_explosions(
+    mut alien_killed: EventReader<AlienKilled>,
+    mut animations: SpriteAnimations,
+    mut commands: Commands,
+) {
+    for killed in alien_killed.read() {
+        let (sprite, animation) = animations.start("explosion");
+        commands.spawn((
+            OnGameScreen,
+            DespawnWhenFinished,
+            sprite,
+            animation,
+            Transform::from_translation(killed.position.extend(20.0)),
+        ));
+    }
+}
+
+fn advance_formation_clock(time: Res<Time>, mut clock: ResMut<FormationClock>) {
+    clock.elapsed += time.delta_secs() * clock.speed;
+}
+
+fn move_aliens(
+    clock: Res<FormationClock>,
     movement_curve: Res<AlienMovementCurve>,
     mut aliens: Query<(&Alien, &mut Transform)>,
 ) {
     for (alien, mut transform) in &mut aliens {
-        let t = (time.elapsed_secs() - alien.setup_time).max(0f32);
-        let next_position = alien.initial_offset + movement_curve.0.sample_clamped(t);
+        let next_position = movement_curve.position(alien, clock.elapsed);
         transform.translation.x = next_position.x;
         transform.translation.y = next_position.y;
     }
@@ -0,0 +1,129 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::alien::{ALIEN_HEIGHT, ALIEN_WIDTH};
+use crate::ron_asset::{register_ron_asset, RonAsset};
+
+/// Name of the built in kind, used when a level names an unknown one.
+pub const DEFAULT_ALIEN_KIND: &str = "alien";
+
+pub struct AlienKindPlugin;
+
+/// How an alien shoots, once the formation's fire timer picked it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+pub enum FirePattern {
+    /// Never shoots, others are picked instead.
+    Never,
+    /// One bullet straight down.
+    #[default]
+    Straight,
+    /// One bullet towards the player.
+    Aimed,
+    /// Three bullets fanning out downwards.
+    Spread,
+}
+
+/// Looks and behaviour of one type of alien.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AlienKind {
+    /// Name of the animation in `assets/*.animations.ron`.
+    pub animation: String,
+    /// Sprite sheet shown once the alien took a hit, but is still alive.
+    /// It has to have the same layout as the animation's sheet.
+    #[serde(default)]
+    pub damaged_sheet: Option<String>,
+    /// Color multiplied with the sprite, as srgb.
+    #[serde(default = "white")]
+    pub color: (f32, f32, f32),
+    /// Size of the sprite and the collider in world units.
+    pub size: (f32, f32),
+    #[serde(default = "one")]
+    pub hit_points: u32,
+    pub points: u32,
+    #[serde(default)]
+    pub fire_pattern: FirePattern,
+}
+
+fn white() -> (f32, f32, f32) {
+    (1.0, 1.0, 1.0)
+}
+
+fn one() -> u32 {
+    1
+}
+
+impl Default for AlienKind {
+    fn default() -> Self {
+        AlienKind {
+            animation: "alien_march".to_string(),
+            damaged_sheet: None,
+            color: white(),
+            size: (ALIEN_WIDTH, ALIEN_HEIGHT),
+            hit_points: 1,
+            points: 10,
+            fire_pattern: FirePattern::Straight,
+        }
+    }
+}
+
+impl AlienKind {
+    pub fn size(&self) -> Vec2 {
+        Vec2::new(self.size.0, self.size.1)
+    }
+
+    pub fn color(&self) -> Color {
+        Color::srgb(self.color.0, self.color.1, self.color.2)
+    }
+}
+
+/// The alien kinds levels can refer to by name, loaded from
+/// `assets/*.aliens.ron`.
+#[derive(Asset, TypePath, Debug, Clone, Deserialize)]
+pub struct AlienKinds {
+    pub kinds: BTreeMap<String, AlienKind>,
+}
+
+impl Default for AlienKinds {
+    fn default() -> Self {
+        AlienKinds {
+            kinds: BTreeMap::from([(DEFAULT_ALIEN_KIND.to_string(), AlienKind::default())]),
+        }
+    }
+}
+
+impl AlienKinds {
+    /// Looks up a kind, falling back to the built in one for unknown names.
+    pub fn get(&self, name: &str) -> AlienKind {
+        self.kinds.get(name).cloned().unwrap_or_else(|| {
+            warn!("unknown alien kind {name:?}, using the default one");
+            AlienKind::default()
+        })
+    }
+}
+
+impl RonAsset for AlienKinds {
+    const EXTENSIONS: &'static [&'static str] = &["aliens.ron"];
+
+    fn validate(&self) -> Result<(), String> {
+        for (name, kind) in &self.kinds {
+            let (width, height) = kind.size;
+            if !(width > 0.0 && height > 0.0 && width <= ALIEN_WIDTH && height <= ALIEN_HEIGHT) {
+                return Err(format!(
+                    "{name}: size has to be positive and fit into {ALIEN_WIDTH}x{ALIEN_HEIGHT}, but is {width}x{height}"
+                ));
+            }
+            if kind.hit_points == 0 {
+                return Err(format!("{name}: hit_points has to be at least 1"));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Plugin for AlienKindPlugin {
+    fn build(&self, app: &mut App) {
+        register_ron_asset::<AlienKinds>(app);
+    }
+}
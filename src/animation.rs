@@ -0,0 +1,279 @@
+use std::collections::{BTreeMap, HashMap};
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::alien::FormationClock;
+use crate::load_assets;
+use crate::ron_asset::{register_ron_asset, RonAsset};
+
+pub struct SpriteAnimationPlugin;
+
+/// When an animation moves on to its next frame.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub enum Timing {
+    /// Every frame is shown for this many seconds.
+    Seconds(f32),
+    /// One frame per step of the alien formation, see
+    /// `FormationClock::march_step`.
+    March,
+}
+
+/// A sprite sheet animation, the sheet is a grid of equally sized frames.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AnimationDef {
+    /// Asset path of the sprite sheet.
+    pub sheet: String,
+    /// Size of one frame in pixels.
+    pub frame_size: (u32, u32),
+    pub columns: u32,
+    #[serde(default = "one")]
+    pub rows: u32,
+    /// Indices of the frames in the order they are shown, counted row by row.
+    pub frames: Vec<usize>,
+    pub timing: Timing,
+    #[serde(default = "yes")]
+    pub looping: bool,
+}
+
+fn one() -> u32 {
+    1
+}
+
+fn yes() -> bool {
+    true
+}
+
+impl AnimationDef {
+    fn new(sheet: &str, frame_size: (u32, u32), frame_count: usize, timing: Timing) -> Self {
+        AnimationDef {
+            sheet: sheet.to_string(),
+            frame_size,
+            columns: frame_count as u32,
+            rows: 1,
+            frames: (0..frame_count).collect(),
+            timing,
+            looping: true,
+        }
+    }
+
+    /// The sheet index to show after `elapsed` frame durations, `None`
+    /// once a one-shot animation is over.
+    fn frame_at(&self, elapsed_frames: usize) -> Option<usize> {
+        if self.looping {
+            Some(self.frames[elapsed_frames % self.frames.len()])
+        } else {
+            self.frames.get(elapsed_frames).copied()
+        }
+    }
+}
+
+/// The animations sprites can use by name, loaded from
+/// `assets/*.animations.ron`.
+#[derive(Asset, TypePath, Debug, Clone, Deserialize)]
+pub struct Animations {
+    pub animations: BTreeMap<String, AnimationDef>,
+}
+
+impl Default for Animations {
+    fn default() -> Self {
+        let explosion = AnimationDef {
+            looping: false,
+            ..AnimationDef::new("explosion.png", (9, 9), 4, Timing::Seconds(0.08))
+        };
+        Animations {
+            animations: BTreeMap::from([
+                (
+                    "alien_march".to_string(),
+                    AnimationDef::new("alien_march.png", (9, 9), 2, Timing::March),
+                ),
+                (
+                    "squid_march".to_string(),
+                    AnimationDef::new("squid_march.png", (9, 9), 2, Timing::March),
+                ),
+                (
+                    "crab_march".to_string(),
+                    AnimationDef::new("crab_march.png", (9, 9), 2, Timing::March),
+                ),
+                (
+                    "octopus_march".to_string(),
+                    AnimationDef::new("octopus_march.png", (9, 9), 2, Timing::March),
+                ),
+                ("explosion".to_string(), explosion),
+                (
+                    "thruster".to_string(),
+                    AnimationDef::new("thruster.png", (3, 4), 3, Timing::Seconds(0.06)),
+                ),
+            ]),
+        }
+    }
+}
+
+impl RonAsset for Animations {
+    const EXTENSIONS: &'static [&'static str] = &["animations.ron"];
+
+    fn validate(&self) -> Result<(), String> {
+        for (name, animation) in &self.animations {
+            let frame_count = (animation.columns * animation.rows) as usize;
+            if animation.frame_size.0 == 0 || animation.frame_size.1 == 0 || frame_count == 0 {
+                return Err(format!("{name}: the sheet needs at least one frame"));
+            }
+            if animation.frames.is_empty() {
+                return Err(format!("{name}: frames must not be empty"));
+            }
+            if let Some(frame) = animation.frames.iter().find(|frame| **frame >= frame_count) {
+                return Err(format!(
+                    "{name}: frame {frame} is not on the sheet of {frame_count} frames"
+                ));
+            }
+            if let Timing::Seconds(seconds) = animation.timing {
+                if !(seconds.is_finite() && seconds > 0.0) {
+                    return Err(format!(
+                        "{name}: frame time has to be positive, but is {seconds}"
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Plays an animation of the `Animations` on the entity's `Sprite`.
+///
+/// The animation is looked up by name on every frame, so edited timings
+/// and frame lists apply to running animations as well.
+#[derive(Component, Debug)]
+pub struct SpriteAnimation {
+    name: String,
+    elapsed: f32,
+}
+
+/// Despawns the entity once its one-shot animation is over.
+#[derive(Component)]
+pub struct DespawnWhenFinished;
+
+/// The built in `Animations`, used while the file isn't loaded.
+#[derive(Resource)]
+struct BuiltinAnimations(Animations);
+
+/// Atlas layouts by frame size and grid, shared by all sheets using them.
+#[derive(Resource, Default)]
+struct AtlasLayouts(HashMap<(UVec2, u32, u32), Handle<TextureAtlasLayout>>);
+
+/// Starts sprite animations.
+#[derive(SystemParam)]
+pub struct SpriteAnimations<'w> {
+    asset_server: Res<'w, AssetServer>,
+    assets: Res<'w, load_assets::Assets>,
+    animations: Res<'w, bevy::prelude::Assets<Animations>>,
+    builtin: Res<'w, BuiltinAnimations>,
+    layouts: ResMut<'w, bevy::prelude::Assets<TextureAtlasLayout>>,
+    atlas_layouts: ResMut<'w, AtlasLayouts>,
+}
+
+impl SpriteAnimations<'_> {
+    /// A sprite showing the first frame of the animation, together with the
+    /// component playing it. Falls back to the built in animations while
+    /// the file isn't loaded.
+    pub fn start(&mut self, name: &str) -> (Sprite, SpriteAnimation) {
+        let animation = SpriteAnimation {
+            name: name.to_string(),
+            elapsed: 0.0,
+        };
+        let animations = loaded_or_builtin(&self.animations, &self.builtin, &self.assets);
+        let Some(def) = animations.animations.get(name) else {
+            warn!("unknown animation {name:?}");
+            return (Sprite::default(), animation);
+        };
+        let frame_size = UVec2::new(def.frame_size.0, def.frame_size.1);
+        let layouts = &mut self.layouts;
+        let layout = self
+            .atlas_layouts
+            .0
+            .entry((frame_size, def.columns, def.rows))
+            .or_insert_with(|| {
+                layouts.add(TextureAtlasLayout::from_grid(
+                    frame_size,
+                    def.columns,
+                    def.rows,
+                    None,
+                    None,
+                ))
+            })
+            .clone();
+        let sprite = Sprite::from_atlas_image(
+            self.asset_server.load(&def.sheet),
+            TextureAtlas {
+                layout,
+                index: def.frames[0],
+            },
+        );
+        (sprite, animation)
+    }
+
+    /// Loads a sheet to swap in for the one of a running animation.
+    pub fn load_sheet(&self, path: &str) -> Handle<Image> {
+        self.asset_server.load(path)
+    }
+}
+
+fn loaded_or_builtin<'a>(
+    animations: &'a bevy::prelude::Assets<Animations>,
+    builtin: &'a BuiltinAnimations,
+    assets: &load_assets::Assets,
+) -> &'a Animations {
+    animations.get(&assets.animations).unwrap_or(&builtin.0)
+}
+
+impl Plugin for SpriteAnimationPlugin {
+    fn build(&self, app: &mut App) {
+        register_ron_asset::<Animations>(app);
+        app.insert_resource(BuiltinAnimations(Animations::default()));
+        app.init_resource::<AtlasLayouts>();
+        app.add_systems(Update, animate_sprites);
+    }
+}
+
+fn animate_sprites(
+    time: Res<Time>,
+    clock: Res<FormationClock>,
+    assets: Option<Res<load_assets::Assets>>,
+    animations: Res<bevy::prelude::Assets<Animations>>,
+    builtin: Res<BuiltinAnimations>,
+    mut animated: Query<(
+        Entity,
+        &mut SpriteAnimation,
+        &mut Sprite,
+        Has<DespawnWhenFinished>,
+    )>,
+    mut commands: Commands,
+) {
+    let Some(assets) = assets else {
+        return;
+    };
+    let animations = loaded_or_builtin(&animations, &builtin, &assets);
+    for (entity, mut animation, mut sprite, despawn_when_finished) in &mut animated {
+        let Some(def) = animations.animations.get(&animation.name) else {
+            continue;
+        };
+        let elapsed_frames = match def.timing {
+            Timing::Seconds(seconds) => {
+                animation.elapsed += time.delta_secs();
+                (animation.elapsed / seconds) as usize
+            }
+            Timing::March => clock.march_step() as usize,
+        };
+        match def.frame_at(elapsed_frames) {
+            Some(index) => {
+                if let Some(atlas) = &mut sprite.texture_atlas {
+                    if atlas.index != index {
+                        atlas.index = index;
+                    }
+                }
+            }
+            None if despawn_when_finished => commands.entity(entity).despawn_recursive(),
+            None => {}
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use std::collections::BTreeMap;
+
+use bevy::asset::AssetLoadFailedEvent;
+use bevy::prelude::*;
+
+use crate::alien_kind::AlienKinds;
+use crate::animation::Animations;
+use crate::config::GameConfig;
+use crate::level::Level;
+use crate::sfxr::SfxrPresets;
+use crate::weapon::Weapons;
+
+const ERROR_COLOR: Color = Color::srgb(1.0, 0.3, 0.3);
+
+/// Shows assets which failed to load (or to reload while hot reloading)
+/// on screen instead of letting the game fall over.
+pub struct AssetErrorsPlugin;
+
+/// Load errors keyed by asset path, so a fixed file clears its own entry.
+#[derive(Resource, Default)]
+pub struct AssetErrors(BTreeMap<String, String>);
+
+impl AssetErrors {
+    /// Reports a problem with an asset which did load, but could not be
+    /// used, e.g. because it does not fit together with another one.
+    pub fn report(&mut self, path: String, error: String) {
+        error!("can't use {path}: {error}");
+        self.0.insert(path, error);
+    }
+}
+
+/// Systems checking loaded assets should run after this set, otherwise the
+/// errors they report are cleared again by the successful load.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TrackAssetErrors;
+
+#[derive(Component)]
+struct AssetErrorText;
+
+impl Plugin for AssetErrorsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AssetErrors>();
+        app.add_systems(Startup, setup_asset_error_text);
+        app.add_systems(
+            Update,
+            (
+                track_asset_errors::<Image>,
+                track_asset_errors::<AudioSource>,
+                track_asset_errors::<Level>,
+                track_asset_errors::<AlienKinds>,
+                track_asset_errors::<Weapons>,
+                track_asset_errors::<Animations>,
+                track_asset_errors::<GameConfig>,
+                track_asset_errors::<SfxrPresets>,
+            )
+                .in_set(TrackAssetErrors),
+        );
+        app.add_systems(
+            PostUpdate,
+            update_asset_error_text.run_if(resource_changed::<AssetErrors>),
+        );
+    }
+}
+
+fn setup_asset_error_text(mut cmd: Commands) {
+    cmd.spawn((
+        AssetErrorText,
+        Text::new(""),
+        TextColor(ERROR_COLOR),
+        TextFont {
+            font_size: 12.0,
+            ..default()
+        },
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(4.0),
+            left: Val::Px(4.0),
+            ..default()
+        },
+        GlobalZIndex(i32::MAX),
+    ));
+}
+
+fn track_asset_errors<A: Asset>(
+    mut failed_events: EventReader<AssetLoadFailedEvent<A>>,
+    mut asset_events: EventReader<AssetEvent<A>>,
+    asset_server: Res<AssetServer>,
+    mut errors: ResMut<AssetErrors>,
+) {
+    for event in failed_events.read() {
+        error!("failed to load {}: {}", event.path, event.error);
+        errors
+            .0
+            .insert(event.path.to_string(), event.error.to_string());
+    }
+    for event in asset_events.read() {
+        let (AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id }) = event
+        else {
+            continue;
+        };
+        let Some(path) = asset_server.get_path(*id) else {
+            continue;
+        };
+        let path = path.to_string();
+        if errors.0.contains_key(&path) {
+            errors.0.remove(&path);
+        }
+    }
+}
+
+fn update_asset_error_text(
+    errors: Res<AssetErrors>,
+    mut text: Single<&mut Text, With<AssetErrorText>>,
+) {
+    text.0 = errors
+        .0
+        .iter()
+        .map(|(path, error)| format!("{path}: {error}"))
+        .collect::<Vec<_>>()
+        .join("\n");
+}
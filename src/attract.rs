@@ -0,0 +1,150 @@
+use bevy::prelude::*;
+
+use crate::config::GameConfig;
+use crate::game::OnGameScreen;
+use crate::load_assets;
+use crate::{GameStates, TEXT_COLOR};
+
+/// A gamepad stick pushed further than this counts as input.
+const STICK_THRESHOLD: f32 = 0.5;
+
+/// Starts a demo game played by the computer when nobody touched the main
+/// menu for a while, then shows the high scores and goes back to the menu.
+/// Any input ends the attract mode right away.
+pub struct AttractModePlugin;
+
+/// Present while the attract mode runs. The demo game is played by the
+/// `AiPilot` and never records its score.
+#[derive(Resource)]
+pub struct AttractMode {
+    /// Runs out when the current part of the attract mode is over.
+    timer: Timer,
+}
+
+/// Seconds the main menu went without input.
+#[derive(Resource, Default)]
+struct IdleTime(f32);
+
+#[derive(Component)]
+struct DemoText;
+
+impl Plugin for AttractModePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<IdleTime>();
+        app.add_systems(OnEnter(GameStates::Menu), reset_idle_time);
+        app.add_systems(
+            Update,
+            start_attract_mode_when_idle.run_if(in_state(GameStates::Menu)),
+        );
+        app.add_systems(
+            OnEnter(GameStates::Game),
+            setup_demo_text.run_if(resource_exists::<AttractMode>),
+        );
+        app.add_systems(
+            OnEnter(GameStates::HighScores),
+            show_high_scores.run_if(resource_exists::<AttractMode>),
+        );
+        app.add_systems(
+            Update,
+            run_attract_mode.run_if(resource_exists::<AttractMode>),
+        );
+    }
+}
+
+/// Whether any key, mouse button or gamepad button was just pressed, or a
+/// gamepad stick pushed.
+fn any_input(
+    keyboard_input: &ButtonInput<KeyCode>,
+    mouse_input: &ButtonInput<MouseButton>,
+    gamepads: &Query<&Gamepad>,
+) -> bool {
+    keyboard_input.get_just_pressed().next().is_some()
+        || mouse_input.get_just_pressed().next().is_some()
+        || gamepads.iter().any(|gamepad| {
+            gamepad.get_just_pressed().next().is_some()
+                || gamepad.left_stick().abs().max_element() > STICK_THRESHOLD
+                || gamepad.right_stick().abs().max_element() > STICK_THRESHOLD
+        })
+}
+
+fn reset_idle_time(mut idle_time: ResMut<IdleTime>) {
+    idle_time.0 = 0.0;
+}
+
+#[allow(clippy::too_many_arguments)]
+fn start_attract_mode_when_idle(
+    time: Res<Time<Real>>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    gamepads: Query<&Gamepad>,
+    config: Res<GameConfig>,
+    mut idle_time: ResMut<IdleTime>,
+    mut game_state: ResMut<NextState<GameStates>>,
+    mut cmd: Commands,
+) {
+    if any_input(&keyboard_input, &mouse_input, &gamepads) {
+        idle_time.0 = 0.0;
+        return;
+    }
+    idle_time.0 += time.delta_secs();
+    if idle_time.0 >= config.attract_idle_time {
+        cmd.insert_resource(AttractMode {
+            timer: Timer::from_seconds(config.attract_demo_duration, TimerMode::Once),
+        });
+        game_state.set(GameStates::Game);
+    }
+}
+
+fn setup_demo_text(mut cmd: Commands, assets: Res<load_assets::Assets>) {
+    cmd.spawn((
+        DemoText,
+        OnGameScreen,
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Percent(30.0),
+            width: Val::Percent(100.0),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+    ))
+    .with_child((
+        Text::new("Demo - drücke eine Taste"),
+        TextFont {
+            font: assets.orbitron_font.clone(),
+            font_size: 24.0,
+            ..default()
+        },
+        TextColor(TEXT_COLOR.with_alpha(0.6)),
+    ));
+}
+
+/// The demo game ended, early or because the aliens won or lost.
+fn show_high_scores(config: Res<GameConfig>, mut attract_mode: ResMut<AttractMode>) {
+    attract_mode.timer = Timer::from_seconds(config.attract_high_scores_duration, TimerMode::Once);
+}
+
+#[allow(clippy::too_many_arguments)]
+fn run_attract_mode(
+    time: Res<Time<Real>>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    gamepads: Query<&Gamepad>,
+    state: Res<State<GameStates>>,
+    mut attract_mode: ResMut<AttractMode>,
+    mut game_state: ResMut<NextState<GameStates>>,
+    mut cmd: Commands,
+) {
+    let over = attract_mode.timer.tick(time.delta()).just_finished();
+    if any_input(&keyboard_input, &mouse_input, &gamepads) {
+        cmd.remove_resource::<AttractMode>();
+        game_state.set(GameStates::Menu);
+    } else if over {
+        match state.get() {
+            GameStates::Game => game_state.set(GameStates::HighScores),
+            _ => {
+                cmd.remove_resource::<AttractMode>();
+                game_state.set(GameStates::Menu);
+            }
+        }
+    }
+}
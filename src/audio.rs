@@ -0,0 +1,319 @@
+use bevy::audio::{SpatialScale, Volume};
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::load_assets;
+use crate::settings::Settings;
+use crate::sfxr::{SfxrPresets, SfxrSound};
+use crate::WORLD_WIDTH;
+
+/// Puts the listener's ears at the left and right edge of the playfield.
+const EAR_GAP: f32 = WORLD_WIDTH;
+/// Keeps every sound on the playfield within one unit of both ears, closer
+/// than rodio starts to attenuate, so sounds are panned but not quieter.
+const SPATIAL_SCALE: SpatialScale = SpatialScale::new_2d(1.0 / WORLD_WIDTH);
+
+/// Plays the sound effects of the game, see `PlaySfxExt::play_sfx`. The
+/// music is played by the `MusicPlugin`.
+pub struct AudioManagerPlugin;
+
+/// Mixer buses, each with its own volume in the `Settings`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bus {
+    Sfx,
+    Music,
+    Ui,
+}
+
+/// Sounds which share a limit of voices playing at once. Starting one more
+/// stops the one of the group which has been playing the longest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SoundGroup {
+    Shots,
+    /// Kept apart from the shots, so clicking the fire button in vain
+    /// never cuts off a shot which did fire.
+    DryFire,
+    Explosions,
+    Jingles,
+    Ui,
+}
+
+impl SoundGroup {
+    fn max_voices(self) -> usize {
+        match self {
+            SoundGroup::Shots => 3,
+            SoundGroup::DryFire => 1,
+            SoundGroup::Explosions => 4,
+            SoundGroup::Jingles => 1,
+            SoundGroup::Ui => 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SoundId {
+    Shot,
+    ChargedShot,
+    /// A shot blocked by the cooldown or the bullet limit.
+    DryFire,
+    AlienDestroyed,
+    PlayerHit,
+    Win,
+    GameOver,
+    /// A menu entry or setting changed.
+    UiSelect,
+    PowerUp,
+}
+
+impl SoundId {
+    /// The recording of the sound, if there is one.
+    fn file(self, assets: &load_assets::Assets) -> Option<Handle<AudioSource>> {
+        let file = match self {
+            SoundId::Shot | SoundId::DryFire | SoundId::UiSelect => &assets.bullet_sound,
+            SoundId::AlienDestroyed => {
+                let variant = rand::thread_rng().gen_range(0..assets.destruction_sound.len());
+                &assets.destruction_sound[variant]
+            }
+            SoundId::PlayerHit => &assets.destruction_sound[0],
+            SoundId::Win => &assets.win_sound,
+            SoundId::GameOver => &assets.game_over_sound,
+            // Only synthesized, from their presets.
+            SoundId::ChargedShot | SoundId::PowerUp => return None,
+        };
+        Some(file.clone())
+    }
+
+    /// Name of the sound in `assets/*.sfxr.ron`.
+    fn preset(self) -> &'static str {
+        match self {
+            SoundId::Shot => "shot",
+            SoundId::ChargedShot => "charged_shot",
+            SoundId::DryFire => "dry_fire",
+            SoundId::AlienDestroyed => "alien_destroyed",
+            SoundId::PlayerHit => "player_hit",
+            SoundId::Win => "win",
+            SoundId::GameOver => "game_over",
+            SoundId::UiSelect => "ui_select",
+            SoundId::PowerUp => "power_up",
+        }
+    }
+
+    fn bus(self) -> Bus {
+        match self {
+            SoundId::UiSelect => Bus::Ui,
+            _ => Bus::Sfx,
+        }
+    }
+
+    fn group(self) -> SoundGroup {
+        match self {
+            SoundId::Shot | SoundId::ChargedShot => SoundGroup::Shots,
+            SoundId::DryFire => SoundGroup::DryFire,
+            SoundId::AlienDestroyed | SoundId::PlayerHit => SoundGroup::Explosions,
+            SoundId::Win | SoundId::GameOver => SoundGroup::Jingles,
+            SoundId::UiSelect => SoundGroup::Ui,
+            SoundId::PowerUp => SoundGroup::Jingles,
+        }
+    }
+
+    /// Playback speed of the recording, which changes the pitch as well.
+    fn speed(self) -> f32 {
+        match self {
+            SoundId::DryFire => 2.0,
+            SoundId::UiSelect => 3.0,
+            _ => 1.0,
+        }
+    }
+
+    /// Chance of the sound to play at all, so the many alien explosions
+    /// don't drown out everything else.
+    fn chance(self) -> f64 {
+        match self {
+            SoundId::AlienDestroyed => 0.5,
+            _ => 1.0,
+        }
+    }
+
+    /// Volume of the recording. The presets have a volume of their own.
+    fn volume(self) -> f32 {
+        match self {
+            SoundId::DryFire => 0.3,
+            SoundId::UiSelect => 0.2,
+            _ => 1.0,
+        }
+    }
+}
+
+/// A playing sound, despawned when it ends.
+#[derive(Component, Debug)]
+struct Voice {
+    bus: Bus,
+    group: SoundGroup,
+    /// Volume of the sound itself, before the bus volume is applied.
+    volume: f32,
+    /// Counts up with every voice, the oldest voice is stolen first.
+    started: u64,
+}
+
+#[derive(Resource, Default)]
+struct VoicesStarted(u64);
+
+pub trait PlaySfxExt {
+    /// Plays a sound on its bus, stealing the oldest voice of its group if
+    /// the group is at its limit.
+    fn play_sfx(&mut self, sound: SoundId);
+
+    /// Plays a sound like `play_sfx`, panned to where it happened if the
+    /// `stereo` setting is on.
+    fn play_sfx_at(&mut self, sound: SoundId, position: Vec2);
+}
+
+impl PlaySfxExt for Commands<'_, '_> {
+    fn play_sfx(&mut self, sound: SoundId) {
+        self.queue(PlaySfx {
+            sound,
+            position: None,
+        });
+    }
+
+    fn play_sfx_at(&mut self, sound: SoundId, position: Vec2) {
+        self.queue(PlaySfx {
+            sound,
+            position: Some(position),
+        });
+    }
+}
+
+struct PlaySfx {
+    sound: SoundId,
+    /// Position in the world the sound comes from.
+    position: Option<Vec2>,
+}
+
+impl Command for PlaySfx {
+    fn apply(self, world: &mut World) {
+        let sound = self.sound;
+        let Some(assets) = world.get_resource::<load_assets::Assets>() else {
+            return;
+        };
+        if !rand::thread_rng().gen_bool(sound.chance()) {
+            return;
+        }
+        let file = sound.file(assets);
+        let synthesize = world.resource::<Settings>().synth_sounds || file.is_none();
+        let preset = world
+            .resource::<Assets<SfxrPresets>>()
+            .get(&assets.sfx_presets)
+            .and_then(|presets| presets.presets.get(sound.preset()))
+            .filter(|_| synthesize)
+            .cloned();
+        let source = match (preset, file) {
+            (Some(preset), _) => {
+                let rng = &mut rand::thread_rng();
+                let synthesized = SfxrSound {
+                    params: preset.vary(rng),
+                    seed: rng.gen(),
+                };
+                Source::Synth(world.resource_mut::<Assets<SfxrSound>>().add(synthesized))
+            }
+            (None, Some(file)) => Source::File(file),
+            // The presets are still loading or failed to load.
+            (None, None) => return,
+        };
+        let group = sound.group();
+
+        let mut voices = world.query::<(Entity, &Voice)>();
+        let mut playing: Vec<_> = voices
+            .iter(world)
+            .filter(|(_, voice)| voice.group == group)
+            .map(|(entity, voice)| (voice.started, entity))
+            .collect();
+        if playing.len() >= group.max_voices() {
+            playing.sort_unstable();
+            let stolen = playing.len() + 1 - group.max_voices();
+            for (_, entity) in playing.into_iter().take(stolen) {
+                world.entity_mut(entity).despawn();
+            }
+        }
+
+        let mut voices_started = world.resource_mut::<VoicesStarted>();
+        voices_started.0 += 1;
+        let started = voices_started.0;
+        let settings = world.resource::<Settings>();
+        let bus_volume = settings.bus_volume(sound.bus());
+        let position = self.position.filter(|_| settings.stereo);
+        let (speed, volume) = match source {
+            Source::File(_) => (sound.speed(), sound.volume()),
+            Source::Synth(_) => (1.0, 1.0),
+        };
+        let mut playback = PlaybackSettings::DESPAWN
+            .with_speed(speed)
+            .with_volume(Volume::new(volume * bus_volume));
+        if position.is_some() {
+            playback = playback
+                .with_spatial(true)
+                .with_spatial_scale(SPATIAL_SCALE);
+        }
+        let mut voice = world.spawn((
+            Voice {
+                bus: sound.bus(),
+                group,
+                volume,
+                started,
+            },
+            playback,
+        ));
+        match source {
+            Source::File(file) => voice.insert(AudioPlayer(file)),
+            Source::Synth(synthesized) => voice.insert(AudioPlayer(synthesized)),
+        };
+        if let Some(position) = position {
+            // Only the horizontal position matters for panning.
+            voice.insert(Transform::from_xyz(position.x, 0.0, 0.0));
+        }
+    }
+}
+
+/// Hears the sounds played at a position, see `PlaySfxExt::play_sfx_at`.
+pub fn stereo_listener() -> SpatialListener {
+    // rodio 0.19 turns the ear farther away from a sound up, not down.
+    // Swapping the ears turns that around.
+    let SpatialListener {
+        left_ear_offset,
+        right_ear_offset,
+    } = SpatialListener::new(EAR_GAP);
+    SpatialListener {
+        left_ear_offset: right_ear_offset,
+        right_ear_offset: left_ear_offset,
+    }
+}
+
+enum Source {
+    File(Handle<AudioSource>),
+    /// A new variation of the sound's preset.
+    Synth(Handle<SfxrSound>),
+}
+
+impl Plugin for AudioManagerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<VoicesStarted>();
+        app.add_systems(
+            Update,
+            apply_bus_volumes.run_if(resource_changed::<Settings>),
+        );
+    }
+}
+
+/// Changes the volume of the voices already playing along with their bus.
+fn apply_bus_volumes(
+    settings: Res<Settings>,
+    voices: Query<(&Voice, &AudioSink)>,
+    spatial_voices: Query<(&Voice, &SpatialAudioSink)>,
+) {
+    for (voice, sink) in &voices {
+        sink.set_volume(voice.volume * settings.bus_volume(voice.bus));
+    }
+    for (voice, sink) in &spatial_voices {
+        sink.set_volume(voice.volume * settings.bus_volume(voice.bus));
+    }
+}
@@ -1,54 +1,136 @@
 use bevy::prelude::*;
 
-use crate::{game::OnGameScreen, load_assets, player::PlayerShot, GameStates, WORLD_HEIGHT};
+use crate::{
+    alien::FormationClock,
+    audio::{PlaySfxExt, SoundId},
+    collision::{layer, Collider, CollisionLayers, Piercing},
+    config::GameConfig,
+    difficulty::Difficulty,
+    game::OnGameScreen,
+    load_assets,
+    particles::{ParticleEffect, ParticleEmitter},
+    player::{Player, PlayerShot},
+    weapon::{ProjectileSpec, Weapon, WeaponCooldown},
+    GameStates, WORLD_HEIGHT, WORLD_WIDTH,
+};
 
 pub struct BulletsPlugin;
 
-const BULLET_SPEED: f32 = 256f32;
+pub const BULLET_WIDTH: f32 = 1f32;
+pub const BULLET_HEIGHT: f32 = 4f32;
+
+/// Charge needed for a charged shot, below that a normal shot is fired.
+const MIN_CHARGE: f32 = 0.25;
 
 #[derive(Component)]
 pub struct Bullet;
 
+/// Shot by the aliens, flying downwards.
+#[derive(Component)]
+pub struct AlienBullet;
+
+/// World units per second.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Velocity(pub Vec2);
+
 impl Plugin for BulletsPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, spawn_bullet.run_if(in_state(GameStates::Game)));
+        app.add_systems(Update, fire_weapon.run_if(in_state(GameStates::Game)));
         app.add_systems(Update, move_bullets.run_if(in_state(GameStates::Game)));
     }
 }
 
-fn spawn_bullet(
+/// The one place deciding whether a `PlayerShot` actually fires, no matter
+/// which input sent it: the weapon has to be cooled down and there must be
+/// fewer player bullets on screen than allowed. Otherwise the shot is a
+/// dry fire, which is only audible for shots the player asked for
+/// explicitly, not for autofire.
+#[allow(clippy::too_many_arguments)]
+fn fire_weapon(
     mut player_shot_event_reader: EventReader<PlayerShot>,
+    player: Single<(&Weapon, &mut WeaponCooldown), With<Player>>,
+    live_bullets: Query<(), With<Bullet>>,
+    time: Res<Time>,
+    config: Res<GameConfig>,
+    difficulty: Res<Difficulty>,
     mut cmd: Commands,
     assets: Res<load_assets::Assets>,
 ) {
+    let (weapon, mut cooldown) = player.into_inner();
+    cooldown.0.tick(time.delta());
     let mut shot_event = None;
     for event in player_shot_event_reader.read() {
         shot_event = Some(event);
     }
-    if let Some(shot_event) = shot_event {
-        let bullet_sound = assets.bullet_sound.clone();
-        let bullet_graphics = assets.bullet.clone();
-        cmd.spawn((
+    let Some(shot_event) = shot_event else {
+        return;
+    };
+    let difficulty = difficulty.settings();
+    let max_bullets = difficulty
+        .max_player_bullets
+        .unwrap_or(config.max_player_bullets);
+    let allowed_bullets = (max_bullets as usize).saturating_sub(live_bullets.iter().count());
+    if !cooldown.0.finished() || allowed_bullets == 0 {
+        if shot_event.auto {
+            return;
+        }
+        cmd.play_sfx_at(SoundId::DryFire, Vec2::new(shot_event.x, shot_event.y));
+        return;
+    }
+    cooldown.0 = Timer::from_seconds(weapon.cooldown, TimerMode::Once);
+
+    let bullet_speed = config.bullet_speed * difficulty.bullet_speed;
+    let charged_shot;
+    let (projectiles, sound) = if shot_event.charge >= MIN_CHARGE {
+        charged_shot = [ProjectileSpec::charged(shot_event.charge)];
+        (&charged_shot[..], SoundId::ChargedShot)
+    } else {
+        (&weapon.projectiles[..], SoundId::Shot)
+    };
+    // A volley with more projectiles than bullets allowed on screen keeps
+    // the ones flying closest to straight up.
+    let mut projectiles: Vec<_> = projectiles.iter().collect();
+    if projectiles.len() > allowed_bullets {
+        projectiles.sort_by(|a, b| a.angle.abs().total_cmp(&b.angle.abs()));
+        projectiles.truncate(allowed_bullets);
+    }
+    for projectile in projectiles {
+        let mut bullet = cmd.spawn((
             Bullet,
             OnGameScreen,
-            Transform::from_xyz(shot_event.x, shot_event.y, 20.0),
+            ParticleEmitter::new(ParticleEffect::TRAIL),
+            Velocity(projectile.direction() * bullet_speed * projectile.speed),
+            Transform::from_xyz(shot_event.x, shot_event.y, 20.0)
+                .with_rotation(Quat::from_rotation_z(projectile.angle.to_radians())),
+            Collider::aabb(
+                Vec2::new(BULLET_WIDTH, BULLET_HEIGHT) * projectile.scale,
+                CollisionLayers::new(layer::PLAYER_BULLET, layer::ALIEN),
+            ),
             Sprite {
-                image: bullet_graphics,
+                image: assets.bullet.clone(),
+                custom_size: Some(Vec2::new(BULLET_WIDTH, BULLET_HEIGHT) * projectile.scale),
                 ..Default::default()
             },
-            AudioPlayer::new(bullet_sound),
         ));
+        if projectile.piercing > 0 {
+            bullet.insert(Piercing(projectile.piercing));
+        }
     }
+    cmd.play_sfx_at(sound, Vec2::new(shot_event.x, shot_event.y));
 }
 
 fn move_bullets(
-    mut bullets: Query<(Entity, &mut Transform), With<Bullet>>,
+    mut bullets: Query<(Entity, &mut Transform, &Velocity, Has<AlienBullet>)>,
     time: Res<Time>,
+    clock: Res<FormationClock>,
     mut cmd: Commands,
 ) {
-    for (entity, mut bullet) in &mut bullets {
-        bullet.translation.y += BULLET_SPEED * time.delta().as_secs_f32();
-        if bullet.translation.y >= WORLD_HEIGHT / 2.0 {
+    for (entity, mut bullet, velocity, is_alien_bullet) in &mut bullets {
+        let time_scale = if is_alien_bullet { clock.speed } else { 1.0 };
+        bullet.translation += (velocity.0 * time.delta_secs() * time_scale).extend(0.0);
+        if bullet.translation.y.abs() >= WORLD_HEIGHT / 2.0
+            || bullet.translation.x.abs() >= WORLD_WIDTH / 2.0
+        {
             cmd.entity(entity).despawn();
         }
     }
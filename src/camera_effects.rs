@@ -0,0 +1,166 @@
+use bevy::prelude::*;
+
+use crate::alien::AlienKilled;
+use crate::config::GameConfig;
+use crate::game::{MainCamera, OnGameScreen};
+use crate::player::PlayerHit;
+use crate::settings::Settings;
+use crate::GameStates;
+
+/// Trauma lost per second, a full shake calms down within a second.
+const TRAUMA_DECAY: f32 = 1.2;
+/// Rotation of the camera at the strongest shake, in radians.
+const MAX_SHAKE_ANGLE: f32 = 0.02;
+const FLASH_COLOR: Color = Color::srgba(1.0, 0.3, 0.2, 0.5);
+
+/// Screen shake, hit-stop and flashes, each of which can be turned off in
+/// the `Settings`.
+pub struct CameraEffectsPlugin;
+
+/// Amount of screen shake, from 0 to 1. The camera offset grows with the
+/// square of it, so small hits barely move the screen while big ones do.
+#[derive(Resource, Default)]
+struct Trauma(f32);
+
+/// Real time left until the game continues after a hit-stop.
+#[derive(Resource, Default)]
+struct HitStop(Option<Timer>);
+
+#[derive(Component)]
+struct Flash(Timer);
+
+impl Plugin for CameraEffectsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Trauma>();
+        app.init_resource::<HitStop>();
+        app.add_systems(
+            Update,
+            (
+                add_trauma,
+                start_hit_stop,
+                start_flash,
+                shake_camera,
+                fade_flash,
+            )
+                .run_if(in_state(GameStates::Game)),
+        );
+        // Runs in every state, so leaving the game during a hit-stop doesn't
+        // leave the time frozen.
+        app.add_systems(Update, end_hit_stop);
+        app.add_systems(OnExit(GameStates::Game), reset_camera);
+    }
+}
+
+fn add_trauma(
+    mut alien_killed: EventReader<AlienKilled>,
+    mut player_hit: EventReader<PlayerHit>,
+    config: Res<GameConfig>,
+    mut trauma: ResMut<Trauma>,
+) {
+    let added = alien_killed.read().count() as f32 * config.shake_alien_killed
+        + player_hit.read().count() as f32 * config.shake_player_hit;
+    if added > 0.0 {
+        trauma.0 = (trauma.0 + added).min(1.0);
+    }
+}
+
+fn shake_camera(
+    time: Res<Time<Real>>,
+    settings: Res<Settings>,
+    config: Res<GameConfig>,
+    mut trauma: ResMut<Trauma>,
+    mut camera: Single<&mut Transform, With<MainCamera>>,
+) {
+    if trauma.0 == 0.0 {
+        return;
+    }
+    trauma.0 = (trauma.0 - TRAUMA_DECAY * time.delta_secs()).max(0.0);
+    if !settings.screen_shake {
+        return;
+    }
+    let shake = trauma.0 * trauma.0;
+    // Sines of unrelated frequencies wobble smoothly, unlike random offsets
+    // which look like jitter at high frame rates.
+    let t = time.elapsed_secs();
+    let noise = |frequency: f32, phase: f32| (t * frequency + phase).sin();
+    camera.translation.x = config.max_shake_offset * shake * noise(47.0, 0.0);
+    camera.translation.y = config.max_shake_offset * shake * noise(53.0, 1.3);
+    camera.rotation = Quat::from_rotation_z(MAX_SHAKE_ANGLE * shake * noise(41.0, 2.1));
+}
+
+fn reset_camera(mut trauma: ResMut<Trauma>, mut camera: Single<&mut Transform, With<MainCamera>>) {
+    trauma.0 = 0.0;
+    camera.translation.x = 0.0;
+    camera.translation.y = 0.0;
+    camera.rotation = Quat::IDENTITY;
+}
+
+/// Freezes the game for a moment when an alien is shot down, which makes
+/// the hit feel heavier.
+fn start_hit_stop(
+    mut alien_killed: EventReader<AlienKilled>,
+    settings: Res<Settings>,
+    config: Res<GameConfig>,
+    mut hit_stop: ResMut<HitStop>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    if alien_killed.read().count() == 0 || !settings.hit_stop || config.hit_stop_duration == 0.0 {
+        return;
+    }
+    hit_stop.0 = Some(Timer::from_seconds(
+        config.hit_stop_duration,
+        TimerMode::Once,
+    ));
+    time.pause();
+}
+
+fn end_hit_stop(
+    real_time: Res<Time<Real>>,
+    state: Res<State<GameStates>>,
+    mut hit_stop: ResMut<HitStop>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    let Some(timer) = &mut hit_stop.0 else {
+        return;
+    };
+    if timer.tick(real_time.delta()).finished() || *state.get() != GameStates::Game {
+        hit_stop.0 = None;
+        time.unpause();
+    }
+}
+
+fn start_flash(
+    mut player_hit: EventReader<PlayerHit>,
+    settings: Res<Settings>,
+    config: Res<GameConfig>,
+    mut commands: Commands,
+) {
+    if player_hit.read().count() == 0 || !settings.flash || config.flash_duration == 0.0 {
+        return;
+    }
+    commands.spawn((
+        Flash(Timer::from_seconds(config.flash_duration, TimerMode::Once)),
+        OnGameScreen,
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            ..default()
+        },
+        BackgroundColor(FLASH_COLOR),
+    ));
+}
+
+fn fade_flash(
+    time: Res<Time<Real>>,
+    mut flashes: Query<(Entity, &mut Flash, &mut BackgroundColor)>,
+    mut commands: Commands,
+) {
+    for (entity, mut flash, mut color) in &mut flashes {
+        if flash.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        color.0 = FLASH_COLOR.with_alpha(FLASH_COLOR.alpha() * flash.0.fraction_remaining());
+    }
+}
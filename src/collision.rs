@@ -1,45 +1,496 @@
+use std::collections::HashSet;
+
 use bevy::prelude::*;
-use rand::Rng;
 
-use crate::alien::Alien;
-use crate::bullet::Bullet;
-use crate::game::OnGameScreen;
-use crate::{load_assets, GameStates};
+use crate::alien::{Alien, AlienKilled};
+use crate::audio::{PlaySfxExt, SoundId};
+use crate::particles::{EmitParticles, ParticleEffect};
+use crate::player::{Lives, PlayerHit};
+use crate::powerup::{ActivePowerUps, PowerUpKind};
+use crate::score::Score;
+use crate::spatial_grid::SpatialGrid;
+use crate::GameStates;
+
+/// Edge length of the cells of the broad phase grid, a bit larger than
+/// the biggest sprites.
+const GRID_CELL_SIZE: f32 = 16f32;
 
 pub struct CollisionPlugin;
 
-const COLLISION_DIST: f32 = 6f32;
+/// Bits for `CollisionLayers`.
+pub mod layer {
+    pub const PLAYER: u32 = 1 << 0;
+    pub const ALIEN: u32 = 1 << 1;
+    pub const PLAYER_BULLET: u32 = 1 << 2;
+    pub const ALIEN_BULLET: u32 = 1 << 3;
+    pub const POWER_UP: u32 = 1 << 4;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColliderShape {
+    Aabb { half_size: Vec2 },
+    Circle { radius: f32 },
+}
+
+/// Two colliders only collide, if each one is in a layer the other one is
+/// looking for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CollisionLayers {
+    /// The layers this collider is part of.
+    pub memberships: u32,
+    /// The layers this collider collides with.
+    pub filters: u32,
+}
+
+impl CollisionLayers {
+    pub const fn new(memberships: u32, filters: u32) -> Self {
+        CollisionLayers {
+            memberships,
+            filters,
+        }
+    }
+
+    pub fn interacts_with(&self, other: &CollisionLayers) -> bool {
+        self.memberships & other.filters != 0 && other.memberships & self.filters != 0
+    }
+}
+
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Collider {
+    pub shape: ColliderShape,
+    /// Offset of the shape's center from the entity's translation.
+    pub offset: Vec2,
+    pub layers: CollisionLayers,
+}
+
+impl Collider {
+    pub fn aabb(size: Vec2, layers: CollisionLayers) -> Self {
+        Collider {
+            shape: ColliderShape::Aabb {
+                half_size: size / 2.0,
+            },
+            offset: Vec2::ZERO,
+            layers,
+        }
+    }
+
+    pub fn circle(radius: f32, layers: CollisionLayers) -> Self {
+        Collider {
+            shape: ColliderShape::Circle { radius },
+            offset: Vec2::ZERO,
+            layers,
+        }
+    }
+
+    pub fn with_offset(mut self, offset: Vec2) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// The axis aligned box enclosing the collider's shape.
+    pub fn bounds(&self, position: Vec2) -> Rect {
+        let half_size = match self.shape {
+            ColliderShape::Aabb { half_size } => half_size,
+            ColliderShape::Circle { radius } => Vec2::splat(radius),
+        };
+        Rect::from_center_half_size(position + self.offset, half_size)
+    }
+
+    pub fn overlaps(&self, position: Vec2, other: &Collider, other_position: Vec2) -> bool {
+        let center = position + self.offset;
+        let other_center = other_position + other.offset;
+        match (self.shape, other.shape) {
+            (
+                ColliderShape::Aabb { half_size },
+                ColliderShape::Aabb {
+                    half_size: other_half,
+                },
+            ) => {
+                let distance = (center - other_center).abs();
+                distance.x <= half_size.x + other_half.x && distance.y <= half_size.y + other_half.y
+            }
+            (
+                ColliderShape::Circle { radius },
+                ColliderShape::Circle {
+                    radius: other_radius,
+                },
+            ) => center.distance_squared(other_center) <= (radius + other_radius).powi(2),
+            (ColliderShape::Aabb { half_size }, ColliderShape::Circle { radius }) => {
+                circle_overlaps_aabb(other_center, radius, center, half_size)
+            }
+            (ColliderShape::Circle { radius }, ColliderShape::Aabb { half_size }) => {
+                circle_overlaps_aabb(center, radius, other_center, half_size)
+            }
+        }
+    }
+}
+
+fn circle_overlaps_aabb(circle: Vec2, radius: f32, aabb: Vec2, half_size: Vec2) -> bool {
+    let closest = circle.clamp(aabb - half_size, aabb + half_size);
+    circle.distance_squared(closest) <= radius * radius
+}
+
+/// A collider at its current position, as handed to the collision search.
+#[derive(Debug, Clone, Copy)]
+pub struct Body {
+    pub collider: Collider,
+    pub position: Vec2,
+}
+
+impl Body {
+    pub fn collides_with(&self, other: &Body) -> bool {
+        self.collider.layers.interacts_with(&other.collider.layers)
+            && self
+                .collider
+                .overlaps(self.position, &other.collider, other.position)
+    }
+}
+
+/// Tests every body against every other one. Fills `pairs` with the
+/// indices of colliding bodies, sorted and with the lower index first.
+///
+/// The reference for the `SpatialGrid`, which has to give the same result.
+pub fn find_collisions_brute_force(bodies: &[Body], pairs: &mut Vec<(usize, usize)>) {
+    pairs.clear();
+    for (a, body) in bodies.iter().enumerate() {
+        for (b, other) in bodies.iter().enumerate().skip(a + 1) {
+            if body.collides_with(other) {
+                pairs.push((a, b));
+            }
+        }
+    }
+}
+
+/// Sent for every pair of overlapping colliders with interacting layers.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct Collision {
+    pub entities: [Entity; 2],
+    pub layers: [u32; 2],
+}
+
+impl Collision {
+    /// Returns the entities of this collision, ordered so the first one is
+    /// part of `first` and the second one part of `second`.
+    pub fn pair(&self, first: u32, second: u32) -> Option<(Entity, Entity)> {
+        let [a, b] = self.entities;
+        let [a_layers, b_layers] = self.layers;
+        if a_layers & first != 0 && b_layers & second != 0 {
+            Some((a, b))
+        } else if b_layers & first != 0 && a_layers & second != 0 {
+            Some((b, a))
+        } else {
+            None
+        }
+    }
+}
+
+/// Lets a projectile pass through this many targets, before it is used up
+/// by the next one. Counts down with every target passed.
+#[derive(Component, Debug, Clone, Copy)]
+#[require(HitTargets)]
+pub struct Piercing(pub u32);
+
+/// Targets a piercing projectile already went through, so they are not hit
+/// again in the following frames while the projectile still overlaps them.
+#[derive(Component, Debug, Default)]
+pub struct HitTargets(Vec<Entity>);
+
+/// A projectile hitting a target, as decided by `resolve_hits`.
+#[derive(Debug, Clone, Copy)]
+pub struct Hit {
+    pub projectile: Entity,
+    pub target: Entity,
+    /// The projectile stops at this target and should be removed.
+    pub projectile_used_up: bool,
+}
+
+/// Decides which of the overlapping (projectile, target) pairs of this tick
+/// are hits, see `decide_hits`.
+pub fn resolve_hits(
+    candidates: impl Iterator<Item = (Entity, Entity)>,
+    transforms: &Query<&Transform>,
+    piercing: &mut Query<(&mut Piercing, &mut HitTargets)>,
+) -> Vec<Hit> {
+    let candidates = candidates
+        .filter_map(|(projectile, target)| {
+            let projectile_position = transforms.get(projectile).ok()?.translation;
+            let target_position = transforms.get(target).ok()?.translation;
+            Some((
+                projectile,
+                target,
+                projectile_position.distance_squared(target_position),
+            ))
+        })
+        .collect();
+    decide_hits(candidates, |projectile, target| {
+        match piercing.get_mut(projectile) {
+            Ok((mut piercing, mut hit_targets)) => pierce(&mut piercing, &mut hit_targets, target),
+            Err(_) => Some(true),
+        }
+    })
+}
+
+/// Decides which of the (projectile, target, distance) candidates are hits.
+/// The nearest pairs are resolved first and every target and every used up
+/// projectile takes part in at most one hit.
+///
+/// `stops_at` tells whether the projectile is used up by the target, or
+/// `None` if it went through the target before and passes it again.
+fn decide_hits(
+    mut candidates: Vec<(Entity, Entity, f32)>,
+    mut stops_at: impl FnMut(Entity, Entity) -> Option<bool>,
+) -> Vec<Hit> {
+    // Sorting by entity as well keeps the result independent of the order
+    // in which the collisions were found.
+    candidates.sort_by(
+        |(projectile_a, target_a, distance_a), (projectile_b, target_b, distance_b)| {
+            distance_a
+                .total_cmp(distance_b)
+                .then(projectile_a.cmp(projectile_b))
+                .then(target_a.cmp(target_b))
+        },
+    );
+
+    let mut resolved_targets = HashSet::new();
+    let mut used_up_projectiles = HashSet::new();
+    let mut hits = Vec::new();
+    for (projectile, target, _) in candidates {
+        if resolved_targets.contains(&target) || used_up_projectiles.contains(&projectile) {
+            continue;
+        }
+        let Some(projectile_used_up) = stops_at(projectile, target) else {
+            continue;
+        };
+        resolved_targets.insert(target);
+        if projectile_used_up {
+            used_up_projectiles.insert(projectile);
+        }
+        hits.push(Hit {
+            projectile,
+            target,
+            projectile_used_up,
+        });
+    }
+    hits
+}
+
+/// A piercing projectile hitting `target`: whether it stops there, or `None`
+/// if it already went through `target`.
+fn pierce(piercing: &mut Piercing, hit_targets: &mut HitTargets, target: Entity) -> Option<bool> {
+    if hit_targets.0.contains(&target) {
+        return None;
+    }
+    hit_targets.0.push(target);
+    if piercing.0 == 0 {
+        Some(true)
+    } else {
+        piercing.0 -= 1;
+        Some(false)
+    }
+}
+
+/// Systems reading `Collision` events should run after this set,
+/// to react in the same frame.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DetectCollisions;
 
 impl Plugin for CollisionPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, detect_collision.run_if(in_state(GameStates::Game)));
+        app.add_event::<Collision>();
+        app.insert_resource(SpatialGrid::new(GRID_CELL_SIZE));
+        app.add_systems(
+            Update,
+            detect_collisions
+                .in_set(DetectCollisions)
+                .run_if(in_state(GameStates::Game)),
+        );
+        app.add_systems(
+            Update,
+            (handle_alien_hits, handle_player_hits)
+                .after(DetectCollisions)
+                .run_if(in_state(GameStates::Game)),
+        );
+    }
+}
+
+fn detect_collisions(
+    colliders: Query<(Entity, &Collider, &Transform)>,
+    mut grid: ResMut<SpatialGrid>,
+    mut entities: Local<Vec<Entity>>,
+    mut bodies: Local<Vec<Body>>,
+    mut pairs: Local<Vec<(usize, usize)>>,
+    mut collisions: EventWriter<Collision>,
+) {
+    entities.clear();
+    bodies.clear();
+    for (entity, collider, transform) in &colliders {
+        entities.push(entity);
+        bodies.push(Body {
+            collider: *collider,
+            position: transform.translation.truncate(),
+        });
+    }
+    grid.find_collisions(&bodies, &mut pairs);
+    for &(a, b) in pairs.iter() {
+        collisions.send(Collision {
+            entities: [entities[a], entities[b]],
+            layers: [
+                bodies[a].collider.layers.memberships,
+                bodies[b].collider.layers.memberships,
+            ],
+        });
     }
 }
 
-fn detect_collision(
-    bullets: Query<(Entity, &Transform), With<Bullet>>,
-    mut aliens: Query<(Entity, &Transform), With<Alien>>,
-    assets: Res<load_assets::Assets>,
+#[allow(clippy::too_many_arguments)]
+fn handle_alien_hits(
+    mut collisions: EventReader<Collision>,
+    transforms: Query<&Transform>,
+    mut piercing: Query<(&mut Piercing, &mut HitTargets)>,
+    mut aliens: Query<(&mut Alien, &mut Sprite)>,
+    mut score: ResMut<Score>,
+    mut alien_killed: EventWriter<AlienKilled>,
+    mut particles: EventWriter<EmitParticles>,
     mut cmd: Commands,
 ) {
-    for (bullet, bullet_transform) in &bullets {
-        for (alien, alien_transform) in &mut aliens {
-            if (alien_transform.translation.x - bullet_transform.translation.x).abs()
-                <= COLLISION_DIST
-                && (alien_transform.translation.y - bullet_transform.translation.y).abs()
-                    <= COLLISION_DIST
-            {
-                cmd.entity(bullet).despawn();
-                cmd.entity(alien).despawn();
-
-                let sound_index = rand::thread_rng().gen_range(0..10);
-                if sound_index < 5 {
-                    cmd.spawn((
-                        OnGameScreen,
-                        AudioPlayer::new(assets.destruction_sound[sound_index].clone()),
-                    ));
-                }
+    let hits = resolve_hits(
+        collisions
+            .read()
+            .filter_map(|collision| collision.pair(layer::PLAYER_BULLET, layer::ALIEN)),
+        &transforms,
+        &mut piercing,
+    );
+    for hit in hits {
+        if hit.projectile_used_up {
+            cmd.entity(hit.projectile).despawn();
+        }
+        let Ok((mut alien, mut sprite)) = aliens.get_mut(hit.target) else {
+            continue;
+        };
+        let Some(points) = alien.take_hit(&mut sprite) else {
+            if let Ok(transform) = transforms.get(hit.projectile) {
+                particles.send(EmitParticles {
+                    effect: ParticleEffect::IMPACT,
+                    position: transform.translation.truncate(),
+                });
             }
+            continue;
+        };
+        cmd.entity(hit.target).despawn();
+        score.points += points;
+        if let Ok(transform) = transforms.get(hit.target) {
+            let position = transform.translation.truncate();
+            alien_killed.send(AlienKilled { position });
+            cmd.play_sfx_at(SoundId::AlienDestroyed, position);
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn handle_player_hits(
+    mut collisions: EventReader<Collision>,
+    transforms: Query<&Transform>,
+    mut piercing: Query<(&mut Piercing, &mut HitTargets)>,
+    mut lives: ResMut<Lives>,
+    active_power_ups: Res<ActivePowerUps>,
+    mut particles: EventWriter<EmitParticles>,
+    mut player_hit: EventWriter<PlayerHit>,
+    mut cmd: Commands,
+) {
+    let hits = resolve_hits(
+        collisions
+            .read()
+            .filter_map(|collision| collision.pair(layer::ALIEN_BULLET, layer::PLAYER)),
+        &transforms,
+        &mut piercing,
+    );
+    for hit in hits {
+        if hit.projectile_used_up {
+            cmd.entity(hit.projectile).despawn();
+        }
+        let Ok(transform) = transforms.get(hit.target) else {
+            continue;
+        };
+        let position = transform.translation.truncate();
+        if active_power_ups.is_active(PowerUpKind::Shield) {
+            particles.send(EmitParticles {
+                effect: ParticleEffect::IMPACT,
+                position,
+            });
+            continue;
         }
+        player_hit.send(PlayerHit { position });
+        lives.0 = lives.0.saturating_sub(1);
+        cmd.play_sfx_at(SoundId::PlayerHit, position);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn entity(index: u32) -> Entity {
+        Entity::from_raw(index)
+    }
+
+    /// Decides the hits of one tick, with `piercing` holding the projectiles
+    /// which pierce.
+    fn decide(
+        candidates: &[(u32, u32, f32)],
+        piercing: &mut HashMap<Entity, (Piercing, HitTargets)>,
+    ) -> Vec<(u32, u32, bool)> {
+        let candidates = candidates
+            .iter()
+            .map(|&(projectile, target, distance)| (entity(projectile), entity(target), distance))
+            .collect();
+        decide_hits(candidates, |projectile, target| {
+            match piercing.get_mut(&projectile) {
+                Some((piercing, hit_targets)) => pierce(piercing, hit_targets, target),
+                None => Some(true),
+            }
+        })
+        .into_iter()
+        .map(|hit| {
+            (
+                hit.projectile.index(),
+                hit.target.index(),
+                hit.projectile_used_up,
+            )
+        })
+        .collect()
+    }
+
+    #[test]
+    fn nearest_target_is_hit_first() {
+        let hits = decide(
+            &[(1, 10, 4.0), (1, 11, 1.0), (1, 12, 9.0)],
+            &mut HashMap::new(),
+        );
+        assert_eq!(hits, [(1, 11, true)]);
+    }
+
+    #[test]
+    fn target_is_hit_once_per_tick() {
+        let hits = decide(&[(1, 10, 4.0), (2, 10, 1.0)], &mut HashMap::new());
+        assert_eq!(hits, [(2, 10, true)]);
+    }
+
+    #[test]
+    fn piercing_projectile_passes_through_its_targets() {
+        let mut piercing = HashMap::from([(entity(1), (Piercing(2), HitTargets::default()))]);
+        let candidates = [(1, 10, 1.0), (1, 11, 2.0), (1, 12, 3.0), (1, 13, 4.0)];
+        let hits = decide(&candidates, &mut piercing);
+        assert_eq!(hits, [(1, 10, false), (1, 11, false), (1, 12, true)]);
+    }
+
+    #[test]
+    fn piercing_projectile_hits_a_target_only_once() {
+        let mut piercing = HashMap::from([(entity(1), (Piercing(2), HitTargets::default()))]);
+        assert_eq!(decide(&[(1, 10, 1.0)], &mut piercing), [(1, 10, false)]);
+        // Still overlapping the target in the next tick.
+        let hits = decide(&[(1, 10, 0.5), (1, 11, 2.0)], &mut piercing);
+        assert_eq!(hits, [(1, 11, false)]);
     }
 }
@@ -0,0 +1,224 @@
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use serde::Deserialize;
+
+use crate::asset_errors::{AssetErrors, TrackAssetErrors};
+use crate::level::Level;
+use crate::load_assets::Assets;
+use crate::ron_asset::{register_ron_asset, RonAsset};
+use crate::{DISPLAY_HEIGHT, DISPLAY_WIDTH, WORLD_HEIGHT};
+
+pub struct ConfigPlugin;
+
+/// Keeps a typo in the star density from spawning millions of stars.
+const MAX_STAR_DENSITY: f32 = 0.05;
+
+/// Gameplay tuning, loaded from `assets/game.config.ron`.
+///
+/// Until the file is loaded (or if it never loads) the defaults are used.
+#[derive(Asset, Resource, TypePath, Debug, Clone, Deserialize)]
+pub struct GameConfig {
+    pub bullet_speed: f32,
+    pub player_max_speed: f32,
+    pub alien_speed: f32,
+    pub alien_gap: f32,
+    pub padding: f32,
+    pub alien_bullet_speed: f32,
+    pub alien_fire_interval: f32,
+    /// Player bullets allowed on screen at once, a new shot is blocked
+    /// until one of them hit something or left the world.
+    pub max_player_bullets: u32,
+    /// Screen shake added when the player is hit, from 0 (none) to 1 (the
+    /// strongest shake).
+    pub shake_player_hit: f32,
+    /// Screen shake added for every alien shot down.
+    pub shake_alien_killed: f32,
+    /// Offset of the camera in world units at the strongest shake.
+    pub max_shake_offset: f32,
+    /// Seconds the game freezes when an alien is shot down.
+    pub hit_stop_duration: f32,
+    /// Seconds the screen flashes when the player is hit.
+    pub flash_duration: f32,
+    /// Stars per square world unit, in each of the background's layers.
+    pub star_density: f32,
+    /// Seconds the music of one state takes to fade into the next.
+    pub music_crossfade: f32,
+    /// Seconds without input in the main menu before the attract mode starts.
+    pub attract_idle_time: f32,
+    /// Longest the demo game of the attract mode runs, in seconds.
+    pub attract_demo_duration: f32,
+    /// Seconds the attract mode shows the high scores after the demo.
+    pub attract_high_scores_duration: f32,
+    /// Seconds the AI pilot of the attract mode takes to notice alien shots
+    /// and pick a new target.
+    pub attract_pilot_reaction_time: f32,
+    /// World units the attract mode pilot's shots miss the aliens by, at most.
+    pub attract_pilot_aim_error: f32,
+    /// Like `attract_pilot_reaction_time`, for the autopilot setting.
+    pub autopilot_reaction_time: f32,
+    /// Like `attract_pilot_aim_error`, for the autopilot setting.
+    pub autopilot_aim_error: f32,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        GameConfig {
+            bullet_speed: 256.0,
+            player_max_speed: 128.0,
+            alien_speed: 96.0,
+            alien_gap: 5.0,
+            padding: 32.0,
+            alien_bullet_speed: 96.0,
+            alien_fire_interval: 1.5,
+            max_player_bullets: 3,
+            shake_player_hit: 0.6,
+            shake_alien_killed: 0.2,
+            max_shake_offset: 4.0,
+            hit_stop_duration: 0.04,
+            flash_duration: 0.3,
+            star_density: 0.002,
+            music_crossfade: 1.0,
+            attract_idle_time: 20.0,
+            attract_demo_duration: 60.0,
+            attract_high_scores_duration: 8.0,
+            attract_pilot_reaction_time: 0.3,
+            attract_pilot_aim_error: 5.0,
+            autopilot_reaction_time: 0.2,
+            autopilot_aim_error: 3.0,
+        }
+    }
+}
+
+fn positive(name: &str, value: f32) -> Result<(), String> {
+    if value.is_finite() && value > 0.0 {
+        Ok(())
+    } else {
+        Err(format!("{name} has to be positive, but is {value}"))
+    }
+}
+
+fn not_negative(name: &str, value: f32) -> Result<(), String> {
+    if value.is_finite() && value >= 0.0 {
+        Ok(())
+    } else {
+        Err(format!("{name} must not be negative, but is {value}"))
+    }
+}
+
+impl RonAsset for GameConfig {
+    const EXTENSIONS: &'static [&'static str] = &["config.ron"];
+
+    fn validate(&self) -> Result<(), String> {
+        positive("bullet_speed", self.bullet_speed)?;
+        positive("player_max_speed", self.player_max_speed)?;
+        positive("alien_speed", self.alien_speed)?;
+        positive("alien_bullet_speed", self.alien_bullet_speed)?;
+        positive("alien_fire_interval", self.alien_fire_interval)?;
+        not_negative("alien_gap", self.alien_gap)?;
+        if self.max_player_bullets == 0 {
+            return Err("max_player_bullets has to be at least 1".to_string());
+        }
+        not_negative("padding", self.padding)?;
+        for (name, trauma) in [
+            ("shake_player_hit", self.shake_player_hit),
+            ("shake_alien_killed", self.shake_alien_killed),
+        ] {
+            if !(0.0..=1.0).contains(&trauma) {
+                return Err(format!("{name} has to be between 0 and 1, but is {trauma}"));
+            }
+        }
+        not_negative("max_shake_offset", self.max_shake_offset)?;
+        not_negative("hit_stop_duration", self.hit_stop_duration)?;
+        not_negative("flash_duration", self.flash_duration)?;
+        not_negative("star_density", self.star_density)?;
+        not_negative("music_crossfade", self.music_crossfade)?;
+        positive("attract_idle_time", self.attract_idle_time)?;
+        positive("attract_demo_duration", self.attract_demo_duration)?;
+        not_negative(
+            "attract_high_scores_duration",
+            self.attract_high_scores_duration,
+        )?;
+        not_negative(
+            "attract_pilot_reaction_time",
+            self.attract_pilot_reaction_time,
+        )?;
+        not_negative("attract_pilot_aim_error", self.attract_pilot_aim_error)?;
+        not_negative("autopilot_reaction_time", self.autopilot_reaction_time)?;
+        not_negative("autopilot_aim_error", self.autopilot_aim_error)?;
+        if self.star_density > MAX_STAR_DENSITY {
+            return Err(format!(
+                "star_density {} is above the maximum of {MAX_STAR_DENSITY}",
+                self.star_density
+            ));
+        }
+        if self.padding >= WORLD_HEIGHT / 2.0 {
+            return Err(format!(
+                "padding {} leaves no room in a world {} high",
+                self.padding, WORLD_HEIGHT
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Plugin for ConfigPlugin {
+    fn build(&self, app: &mut App) {
+        register_ron_asset::<GameConfig>(app);
+        app.init_resource::<GameConfig>();
+        app.add_systems(Update, apply_config.after(TrackAssetErrors));
+        app.add_systems(Update, resize_window.run_if(resource_changed::<GameConfig>));
+    }
+}
+
+/// Copies the loaded config into the `GameConfig` resource, whenever the
+/// file has been (re)loaded and fits the current level.
+fn apply_config(
+    mut config_events: EventReader<AssetEvent<GameConfig>>,
+    assets: Option<Res<Assets>>,
+    configs: Res<bevy::prelude::Assets<GameConfig>>,
+    levels: Res<bevy::prelude::Assets<Level>>,
+    asset_server: Res<AssetServer>,
+    mut asset_errors: ResMut<AssetErrors>,
+    mut config: ResMut<GameConfig>,
+) {
+    let Some(assets) = assets else {
+        return;
+    };
+    let reloaded = config_events.read().any(|event| {
+        event.is_loaded_with_dependencies(&assets.config) || event.is_modified(&assets.config)
+    });
+    if !reloaded {
+        return;
+    }
+    let Some(new_config) = configs.get(&assets.config) else {
+        return;
+    };
+    let level = levels.get(&assets.level).cloned().unwrap_or_default();
+    let path = asset_server
+        .get_path(&assets.config)
+        .map(|path| path.to_string())
+        .unwrap_or_default();
+    match level.check_fits(new_config) {
+        Ok(()) => *config = new_config.clone(),
+        Err(error) => asset_errors.report(path, format!("invalid values: {error}")),
+    }
+}
+
+/// Fits the window to the padding once the config file has loaded. Later
+/// reloads leave the window alone, so a size the player picked is kept, the
+/// `ScalingPlugin` fits the game into any window anyway.
+fn resize_window(
+    config: Res<GameConfig>,
+    mut resized: Local<bool>,
+    mut window: Single<&mut Window, With<PrimaryWindow>>,
+) {
+    // The window was created for the built-in config already.
+    if config.is_added() || *resized {
+        return;
+    }
+    *resized = true;
+    window.resolution.set(
+        DISPLAY_WIDTH + 2.0 * config.padding,
+        DISPLAY_HEIGHT + 2.0 * config.padding,
+    );
+}
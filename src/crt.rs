@@ -0,0 +1,288 @@
+use bevy::core_pipeline::bloom::Bloom;
+use bevy::core_pipeline::core_2d::graph::{Core2d, Node2d};
+use bevy::core_pipeline::fullscreen_vertex_shader::fullscreen_shader_vertex_state;
+use bevy::ecs::query::QueryItem;
+use bevy::image::BevyDefault;
+use bevy::prelude::*;
+use bevy::render::extract_component::{
+    ComponentUniforms, DynamicUniformIndex, ExtractComponent, ExtractComponentPlugin,
+    UniformComponentPlugin,
+};
+use bevy::render::render_graph::{
+    NodeRunError, RenderGraphApp, RenderGraphContext, RenderLabel, ViewNode, ViewNodeRunner,
+};
+use bevy::render::render_resource::binding_types::{sampler, texture_2d, uniform_buffer};
+use bevy::render::render_resource::*;
+use bevy::render::renderer::{RenderContext, RenderDevice};
+use bevy::render::view::ViewTarget;
+use bevy::render::RenderApp;
+
+use crate::config::GameConfig;
+use crate::game::MainCamera;
+use crate::scaling::playfield_size;
+use crate::settings::Settings;
+
+const SHADER_ASSET_PATH: &str = "shaders/crt.wgsl";
+
+/// An arcade cabinet look for the main camera: scanlines, a curved screen,
+/// the colored overlay strips of the original and phosphor bloom. Turned
+/// on and off with the `crt` setting in the settings menu.
+pub struct CrtPlugin;
+
+/// Adds the CRT pass to a camera.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Crt {
+    /// How much darker the gaps between the scanlines are, from 0 to 1.
+    pub scanline_strength: f32,
+    /// Barrel distortion, 0 is a flat screen.
+    pub curvature: f32,
+    /// How strongly the overlay strips tint the image, from 0 to 1.
+    pub overlay_strength: f32,
+    /// Number of scanlines over the height of the viewport.
+    pub scanlines: f32,
+}
+
+impl Default for Crt {
+    fn default() -> Self {
+        Crt {
+            scanline_strength: 0.35,
+            curvature: 0.06,
+            overlay_strength: 0.8,
+            scanlines: playfield_size(&GameConfig::default()).y,
+        }
+    }
+}
+
+/// `Crt` as handed to the shader, with the camera's viewport relative to
+/// its render target.
+mod uniform {
+    // The `ShaderType` derive generates size checks the compiler considers
+    // unused.
+    #![allow(dead_code)]
+
+    use bevy::prelude::*;
+    use bevy::render::render_resource::ShaderType;
+
+    #[derive(Component, Debug, Clone, Copy, ShaderType)]
+    pub struct CrtUniform {
+        pub viewport_origin: Vec2,
+        pub viewport_size: Vec2,
+        pub scanline_strength: f32,
+        pub curvature: f32,
+        pub overlay_strength: f32,
+        pub scanlines: f32,
+    }
+}
+
+use uniform::CrtUniform;
+
+impl ExtractComponent for Crt {
+    type QueryData = (&'static Crt, &'static Camera);
+    type QueryFilter = ();
+    type Out = CrtUniform;
+
+    fn extract_component((crt, camera): QueryItem<'_, Self::QueryData>) -> Option<CrtUniform> {
+        let target_size = camera.physical_target_size()?.as_vec2();
+        let viewport = camera.physical_viewport_rect()?;
+        Some(CrtUniform {
+            viewport_origin: viewport.min.as_vec2() / target_size,
+            viewport_size: viewport.size().as_vec2() / target_size,
+            scanline_strength: crt.scanline_strength,
+            curvature: crt.curvature,
+            overlay_strength: crt.overlay_strength,
+            scanlines: crt.scanlines,
+        })
+    }
+}
+
+impl Plugin for CrtPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            ExtractComponentPlugin::<Crt>::default(),
+            UniformComponentPlugin::<CrtUniform>::default(),
+        ));
+        app.add_systems(Update, apply_crt_setting);
+
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app
+            .add_render_graph_node::<ViewNodeRunner<CrtNode>>(Core2d, CrtLabel)
+            .add_render_graph_edges(
+                Core2d,
+                (
+                    Node2d::Tonemapping,
+                    CrtLabel,
+                    Node2d::EndMainPassPostProcessing,
+                ),
+            );
+    }
+
+    fn finish(&self, app: &mut App) {
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        render_app.init_resource::<CrtPipeline>();
+    }
+}
+
+/// Adds or removes the effect. Bloom needs HDR, without the effect the
+/// camera goes back to plain rendering.
+fn apply_crt_setting(
+    settings: Res<Settings>,
+    config: Res<GameConfig>,
+    camera: Single<(Entity, &mut Camera, Has<Crt>), With<MainCamera>>,
+    mut commands: Commands,
+) {
+    let (entity, mut camera, has_crt) = camera.into_inner();
+    if settings.crt {
+        if !has_crt || config.is_changed() {
+            camera.hdr = true;
+            commands.entity(entity).insert((
+                Crt {
+                    scanlines: playfield_size(&config).y,
+                    ..default()
+                },
+                Bloom {
+                    intensity: 0.2,
+                    ..Bloom::OLD_SCHOOL
+                },
+            ));
+        }
+    } else if has_crt {
+        camera.hdr = false;
+        commands.entity(entity).remove::<(Crt, Bloom)>();
+    }
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
+struct CrtLabel;
+
+#[derive(Default)]
+struct CrtNode;
+
+impl ViewNode for CrtNode {
+    type ViewQuery = (
+        &'static ViewTarget,
+        &'static CrtUniform,
+        &'static DynamicUniformIndex<CrtUniform>,
+    );
+
+    fn run(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        (view_target, _crt, uniform_index): QueryItem<Self::ViewQuery>,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let crt_pipeline = world.resource::<CrtPipeline>();
+        let pipeline_id = if view_target.is_hdr() {
+            crt_pipeline.hdr_pipeline
+        } else {
+            crt_pipeline.pipeline
+        };
+        let Some(pipeline) = world
+            .resource::<PipelineCache>()
+            .get_render_pipeline(pipeline_id)
+        else {
+            // Still compiling.
+            return Ok(());
+        };
+        let Some(uniforms) = world
+            .resource::<ComponentUniforms<CrtUniform>>()
+            .uniforms()
+            .binding()
+        else {
+            return Ok(());
+        };
+
+        let post_process = view_target.post_process_write();
+        let bind_group = render_context.render_device().create_bind_group(
+            "crt_bind_group",
+            &crt_pipeline.layout,
+            &BindGroupEntries::sequential((
+                post_process.source,
+                &crt_pipeline.sampler,
+                uniforms.clone(),
+            )),
+        );
+        let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+            label: Some("crt_pass"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view: post_process.destination,
+                resolve_target: None,
+                ops: Operations::default(),
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        render_pass.set_render_pipeline(pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[uniform_index.index()]);
+        render_pass.draw(0..3, 0..1);
+        Ok(())
+    }
+}
+
+#[derive(Resource)]
+struct CrtPipeline {
+    layout: BindGroupLayout,
+    sampler: Sampler,
+    pipeline: CachedRenderPipelineId,
+    /// The same for HDR cameras, whose textures have a different format.
+    hdr_pipeline: CachedRenderPipelineId,
+}
+
+impl FromWorld for CrtPipeline {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+        let layout = render_device.create_bind_group_layout(
+            "crt_bind_group_layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::FRAGMENT,
+                (
+                    texture_2d(TextureSampleType::Float { filterable: true }),
+                    sampler(SamplerBindingType::Filtering),
+                    uniform_buffer::<CrtUniform>(true),
+                ),
+            ),
+        );
+        let sampler = render_device.create_sampler(&SamplerDescriptor {
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            ..default()
+        });
+        let shader = world.load_asset(SHADER_ASSET_PATH);
+        let descriptor = |format: TextureFormat| RenderPipelineDescriptor {
+            label: Some("crt_pipeline".into()),
+            layout: vec![layout.clone()],
+            vertex: fullscreen_shader_vertex_state(),
+            fragment: Some(FragmentState {
+                shader: shader.clone(),
+                shader_defs: vec![],
+                entry_point: "fragment".into(),
+                targets: vec![Some(ColorTargetState {
+                    format,
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            push_constant_ranges: vec![],
+            zero_initialize_workgroup_memory: false,
+        };
+        let pipeline_cache = world.resource_mut::<PipelineCache>();
+        let pipeline =
+            pipeline_cache.queue_render_pipeline(descriptor(TextureFormat::bevy_default()));
+        let hdr_pipeline =
+            pipeline_cache.queue_render_pipeline(descriptor(ViewTarget::TEXTURE_FORMAT_HDR));
+        CrtPipeline {
+            layout,
+            sampler,
+            pipeline,
+            hdr_pipeline,
+        }
+    }
+}
@@ -3,11 +3,17 @@ use std::time::Duration;
 use bevy::prelude::*;
 
 use crate::{
-    alien::{Alien, ALIEN_HEIGHT},
-    despawn_component_type, load_assets,
-    player::PLAYER_HEIGHT,
+    alien::Alien,
+    attract::AttractMode,
+    audio::{PlaySfxExt, SoundId},
+    config::GameConfig,
+    despawn_component_type,
+    difficulty::Difficulty,
+    load_assets,
+    player::Lives,
+    score::{HighScores, Score},
     start_game_on_button_press::start_game_on_button_press,
-    GameStates, PADDING, TEXT_COLOR, WORLD_HEIGHT,
+    GameStates, TEXT_COLOR, WORLD_HEIGHT,
 };
 
 pub struct DetectWinOrLossPlugin;
@@ -43,7 +49,7 @@ impl Plugin for DetectWinOrLossPlugin {
         app.add_systems(OnEnter(GameStates::End), setup_end_screen);
         app.add_systems(
             Update,
-            start_game_on_button_press.run_if(in_state(EndPhase::Abortable)),
+            (start_game_on_button_press, return_to_menu).run_if(in_state(EndPhase::Abortable)),
         );
         app.add_systems(
             Update,
@@ -64,25 +70,32 @@ struct EndState {
 fn detect_win_or_loss(
     aliens: Query<(Entity, &Transform), With<Alien>>,
     mut game_state: ResMut<NextState<GameStates>>,
+    config: Res<GameConfig>,
+    difficulty: Res<Difficulty>,
+    lives: Res<Lives>,
+    attract_mode: Option<Res<AttractMode>>,
     mut cmd: Commands,
 ) {
-    if aliens.is_empty() {
-        game_state.set(GameStates::End);
-        cmd.insert_resource(EndState {
-            player_did_win: true,
-        });
-    }
-    for (_, transform) in &aliens {
-        if transform.translation.y
-            <= -WORLD_HEIGHT / 2.0 + ALIEN_HEIGHT / 2.0 + PLAYER_HEIGHT / 2.0 + PADDING
-        {
-            game_state.set(GameStates::End);
-            cmd.insert_resource(EndState {
-                player_did_win: false,
-            });
-            break;
-        }
+    let loss_height = difficulty.settings().loss_height(config.padding);
+    let player_did_win = if lives.0 == 0 {
+        false
+    } else if aliens.is_empty() {
+        true
+    } else if aliens
+        .iter()
+        .any(|(_, transform)| transform.translation.y <= -WORLD_HEIGHT / 2.0 + loss_height)
+    {
+        false
+    } else {
+        return;
+    };
+    // A demo game skips the end screen, so its score is never recorded.
+    if attract_mode.is_some() {
+        game_state.set(GameStates::HighScores);
+        return;
     }
+    game_state.set(GameStates::End);
+    cmd.insert_resource(EndState { player_did_win });
 }
 
 #[derive(Resource)]
@@ -91,18 +104,29 @@ struct EndScreenMinimumDisplayTimer(Timer);
 #[derive(Component)]
 struct UpdateableText;
 
-fn setup_end_screen(mut cmd: Commands, assets: Res<load_assets::Assets>, end_state: Res<EndState>) {
+fn setup_end_screen(
+    mut cmd: Commands,
+    assets: Res<load_assets::Assets>,
+    end_state: Res<EndState>,
+    score: Res<Score>,
+    high_scores: Res<HighScores>,
+) {
+    // The score of this game might not be recorded yet.
+    let best = high_scores
+        .best(score.difficulty)
+        .unwrap_or_default()
+        .max(score.points);
     let text = Text::new(if end_state.player_did_win {
         "Super! Du hast gewonnen!"
     } else {
         "Ach, schade, Du hast verloren!"
     });
     cmd.remove_resource::<EndState>();
-    let sound = if end_state.player_did_win {
-        assets.win_sound.clone()
+    cmd.play_sfx(if end_state.player_did_win {
+        SoundId::Win
     } else {
-        assets.game_over_sound.clone()
-    };
+        SoundId::GameOver
+    });
 
     cmd.spawn((
         OnEndScreen,
@@ -130,9 +154,24 @@ fn setup_end_screen(mut cmd: Commands, assets: Res<load_assets::Assets>, end_sta
                 ..default()
             },
         ));
+        p.spawn((
+            Text::new(format!(
+                "{} Punkte, Rekord: {} ({})",
+                score.points,
+                best,
+                score.difficulty.name()
+            )),
+            TextFont {
+                font: assets.orbitron_font.clone(),
+                font_size: 24.0,
+                ..Default::default()
+            },
+            TextColor(TEXT_COLOR),
+        ));
         p.spawn((
             UpdateableText,
             Text::new("."),
+            TextLayout::new_with_justify(JustifyText::Center),
             TextFont {
                 font: assets.orbitron_font.clone(),
                 font_size: 32.0,
@@ -144,7 +183,6 @@ fn setup_end_screen(mut cmd: Commands, assets: Res<load_assets::Assets>, end_sta
                 ..default()
             },
         ));
-        p.spawn(AudioPlayer::new(sound));
     });
     cmd.insert_resource(EndScreenMinimumDisplayTimer(Timer::new(
         Duration::from_secs(1),
@@ -180,9 +218,24 @@ fn update_end_screen(
             Steps::Three => {
                 next_state.set(EndPhase::Abortable);
                 *text_writer.text(*updateable_text, 0) =
-                    "Drücke den Schießenknopf, um es nochmal zu probieren!".to_string();
+                    "Drücke den Schießenknopf, um es nochmal zu probieren!\nZurück ins Menü mit Esc."
+                        .to_string();
                 cmd.remove_resource::<EndScreenMinimumDisplayTimer>();
             }
         }
     }
 }
+
+fn return_to_menu(
+    gamepads: Query<&Gamepad>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut game_state: ResMut<NextState<GameStates>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Escape)
+        || gamepads
+            .iter()
+            .any(|gamepad| gamepad.just_pressed(GamepadButton::East))
+    {
+        game_state.set(GameStates::Menu);
+    }
+}
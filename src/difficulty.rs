@@ -0,0 +1,133 @@
+use bevy::prelude::*;
+
+use crate::alien::ALIEN_HEIGHT;
+use crate::player::PLAYER_HEIGHT;
+
+/// Difficulty chosen in the menu.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+    Arcade,
+}
+
+/// Multipliers applied over the values from the `GameConfig`.
+#[derive(Debug, Clone, Copy)]
+pub struct DifficultySettings {
+    /// Speed of the alien formation along its movement curve.
+    pub alien_speed: f32,
+    /// Height of the top row of aliens at the start of a wave,
+    /// relative to the upper edge of the world.
+    pub formation_height: f32,
+    /// Speed of the player's bullets.
+    pub bullet_speed: f32,
+    /// Height above the lower edge of the world, at which the aliens
+    /// have landed and the game is lost, see `loss_height`.
+    pub loss_threshold: f32,
+    /// How often the aliens shoot back.
+    pub alien_fire_rate: f32,
+    /// Not a multiplier, the number of hits the player can take.
+    pub lives: u32,
+    /// Overrides the configured maximum of player bullets on screen.
+    pub max_player_bullets: Option<u32>,
+}
+
+impl DifficultySettings {
+    /// Height above the lower edge of the world, at which the aliens have
+    /// landed. Never below the top of the player's ship, which sits
+    /// `padding` above the edge, so the aliens can't slip past it.
+    pub fn loss_height(&self, padding: f32) -> f32 {
+        ((ALIEN_HEIGHT / 2.0 + PLAYER_HEIGHT / 2.0 + padding) * self.loss_threshold)
+            .max(padding + PLAYER_HEIGHT / 2.0)
+    }
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 4] = [
+        Difficulty::Easy,
+        Difficulty::Normal,
+        Difficulty::Hard,
+        Difficulty::Arcade,
+    ];
+
+    pub fn settings(self) -> DifficultySettings {
+        match self {
+            Difficulty::Easy => DifficultySettings {
+                alien_speed: 0.75,
+                formation_height: 1.0,
+                bullet_speed: 1.25,
+                loss_threshold: 0.75,
+                alien_fire_rate: 0.5,
+                lives: 5,
+                max_player_bullets: None,
+            },
+            Difficulty::Normal => DifficultySettings {
+                alien_speed: 1.0,
+                formation_height: 1.0,
+                bullet_speed: 1.0,
+                loss_threshold: 1.0,
+                alien_fire_rate: 1.0,
+                lives: 3,
+                max_player_bullets: None,
+            },
+            Difficulty::Hard => DifficultySettings {
+                alien_speed: 1.3,
+                formation_height: 0.85,
+                bullet_speed: 1.0,
+                loss_threshold: 1.5,
+                alien_fire_rate: 1.5,
+                lives: 2,
+                max_player_bullets: None,
+            },
+            Difficulty::Arcade => DifficultySettings {
+                alien_speed: 1.15,
+                formation_height: 0.9,
+                bullet_speed: 0.9,
+                loss_threshold: 1.0,
+                alien_fire_rate: 1.25,
+                lives: 3,
+                // Like the original: one bullet on screen.
+                max_player_bullets: Some(1),
+            },
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Leicht",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Schwer",
+            Difficulty::Arcade => "Arcade",
+        }
+    }
+
+    pub fn next(self) -> Difficulty {
+        let index = Difficulty::ALL.iter().position(|d| *d == self).unwrap();
+        Difficulty::ALL[(index + 1).min(Difficulty::ALL.len() - 1)]
+    }
+
+    pub fn previous(self) -> Difficulty {
+        let index = Difficulty::ALL.iter().position(|d| *d == self).unwrap();
+        Difficulty::ALL[index.saturating_sub(1)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loss_line_stays_above_the_player() {
+        for difficulty in Difficulty::ALL {
+            for padding in [0.0, 8.0, 32.0] {
+                let loss_height = difficulty.settings().loss_height(padding);
+                assert!(
+                    loss_height >= padding + PLAYER_HEIGHT / 2.0,
+                    "{difficulty:?} with padding {padding}: {loss_height}"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,555 @@
+//! The game as a reinforcement learning environment, in the manner of gym.
+//!
+//! The environment runs without a window, renderer or sound output, and
+//! steps the game by a fixed 1/60 s per frame, as fast as the computer can.
+//! Its systems run one after the other in a fixed order, so a game plays out
+//! the same for the same seed and actions.
+//!
+//! The data files are loaded from `EnvConfig::assets_path`. The default,
+//! `assets`, is found when the program runs through cargo. A program run on
+//! its own needs the path to the game's `assets` folder, or the folder
+//! containing it in the `BEVY_ASSET_ROOT` environment variable.
+//!
+//! ```no_run
+//! use space_invaders_demo::env::{Action, EnvConfig, Environment};
+//!
+//! let mut env = Environment::new(EnvConfig::default()).unwrap();
+//! let _observation = env.reset(42);
+//! let mut total_reward = 0.0;
+//! loop {
+//!     let (_observation, reward, done) = env.step(Action {
+//!         movement: 1.0,
+//!         fire: true,
+//!     });
+//!     total_reward += reward;
+//!     if done {
+//!         break;
+//!     }
+//! }
+//! ```
+
+use std::time::{Duration, Instant};
+
+use bevy::app::PluginsState;
+use bevy::asset::LoadState;
+use bevy::ecs::schedule::ExecutorKind;
+use bevy::prelude::*;
+use bevy::render::settings::WgpuSettings;
+use bevy::render::RenderPlugin;
+use bevy::time::TimeUpdateStrategy;
+use bevy::window::ExitCondition;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::collision::{layer, Collider};
+use crate::config::GameConfig;
+use crate::difficulty::Difficulty;
+use crate::game::GameRng;
+use crate::load_assets;
+use crate::player::{Lives, Player, PlayerController, PlayerShot, PLAYER_HEIGHT};
+use crate::score::Score;
+use crate::settings::Settings;
+use crate::{add_game, GameStates, WORLD_HEIGHT, WORLD_WIDTH};
+
+/// Game time simulated per frame.
+const FRAME: Duration = Duration::from_nanos(1_000_000_000 / 60);
+/// Real time the data files may take to load.
+const LOAD_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The collision layers shown in the layers of a `Grid`, in this order.
+pub const GRID_LAYERS: [u32; 5] = [
+    layer::PLAYER,
+    layer::ALIEN,
+    layer::PLAYER_BULLET,
+    layer::ALIEN_BULLET,
+    layer::POWER_UP,
+];
+
+#[derive(Debug, Clone)]
+pub struct EnvConfig {
+    pub difficulty: Difficulty,
+    pub observation: ObservationKind,
+    /// Frames simulated by one `step`, the action is repeated for each.
+    pub frames_per_step: u32,
+    /// Subtracted from the reward for every life lost. Shooting an alien
+    /// down rewards its points.
+    pub life_penalty: f32,
+    /// Folder the data files and sprites are loaded from. Relative paths
+    /// start at the crate when run through cargo and next to the executable
+    /// otherwise, see `AssetPlugin::file_path`.
+    pub assets_path: String,
+}
+
+impl Default for EnvConfig {
+    fn default() -> Self {
+        EnvConfig {
+            difficulty: Difficulty::default(),
+            observation: ObservationKind::Features,
+            frames_per_step: 4,
+            life_penalty: 100.0,
+            assets_path: AssetPlugin::default().file_path,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObservationKind {
+    /// The playfield rasterized into `width` x `height` cells.
+    Grid { width: usize, height: usize },
+    /// The positions of the player, the aliens, the bullets and power-ups.
+    Features,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Observation {
+    Grid(Grid),
+    Features(Features),
+}
+
+/// The playfield as seen from above at a low resolution, one layer per
+/// entry of `GRID_LAYERS`. A cell is 1 where something of that layer
+/// covers it and 0 elsewhere.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Grid {
+    pub width: usize,
+    pub height: usize,
+    /// Layer by layer, each one row by row from the top.
+    pub cells: Vec<f32>,
+}
+
+impl Grid {
+    pub fn get(&self, layer: usize, x: usize, y: usize) -> f32 {
+        self.cells[(layer * self.height + y) * self.width + x]
+    }
+}
+
+/// Positions scaled to -1 to 1 across the world, y pointing up.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Features {
+    /// Stays where the ship was last, once the game is over.
+    pub player_x: f32,
+    pub lives: u32,
+    pub aliens: Vec<Vec2>,
+    pub player_bullets: Vec<Vec2>,
+    pub alien_bullets: Vec<Vec2>,
+    pub power_ups: Vec<Vec2>,
+}
+
+/// What the agent does for the frames of one step.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Action {
+    /// From -1 (full speed to the left) to 1 (full speed to the right).
+    pub movement: f32,
+    /// Shoots whenever the weapon allows it, like autofire.
+    pub fire: bool,
+}
+
+#[derive(Resource, Default)]
+struct AgentAction(Action);
+
+pub struct Environment {
+    app: App,
+    config: EnvConfig,
+    /// Score and lives at the end of the last step, to reward the changes.
+    points: u32,
+    lives: u32,
+    player_x: f32,
+}
+
+impl Environment {
+    /// Sets up the game and waits for its data files to load. Fails if one
+    /// of them is broken.
+    pub fn new(config: EnvConfig) -> Result<Environment, String> {
+        let mut app = App::new();
+        app.add_plugins(
+            DefaultPlugins
+                .set(WindowPlugin {
+                    // Never opened without the WinitPlugin, but the game
+                    // fits its playfield to it.
+                    primary_window: Some(Window::default()),
+                    exit_condition: ExitCondition::DontExit,
+                    close_when_requested: false,
+                })
+                .set(AssetPlugin {
+                    file_path: config.assets_path.clone(),
+                    ..default()
+                })
+                .set(RenderPlugin {
+                    render_creation: WgpuSettings {
+                        backends: None,
+                        ..default()
+                    }
+                    .into(),
+                    ..default()
+                })
+                .disable::<bevy::winit::WinitPlugin>()
+                .disable::<bevy::audio::AudioPlugin>()
+                .disable::<bevy::gilrs::GilrsPlugin>()
+                // Left to the program using the environment.
+                .disable::<bevy::log::LogPlugin>(),
+        );
+        // The recordings are still loaded, though never played.
+        app.init_asset::<AudioSource>();
+        add_game(&mut app);
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(FRAME))
+            .insert_resource(config.difficulty)
+            // Freezing the game on hits would only stretch the steps.
+            .insert_resource(Settings {
+                hit_stop: false,
+                ..default()
+            })
+            .init_resource::<AgentAction>()
+            .add_systems(Update, drive_agent.run_if(in_state(GameStates::Game)));
+
+        while app.plugins_state() == PluginsState::Adding {
+            bevy::tasks::tick_global_task_pools_on_main_thread();
+        }
+        app.finish();
+        app.cleanup();
+        // Systems drawing from the `GameRng`, or reading the shots of the
+        // agent, aren't ordered against each other. Running them on one
+        // thread keeps their order the same in every frame and every game.
+        for (_, schedule) in app.world_mut().resource_mut::<Schedules>().iter_mut() {
+            schedule.set_executor_kind(ExecutorKind::SingleThreaded);
+        }
+
+        let mut env = Environment {
+            app,
+            config,
+            points: 0,
+            lives: 0,
+            player_x: 0.0,
+        };
+        env.wait_for_data_files()?;
+        Ok(env)
+    }
+
+    fn wait_for_data_files(&mut self) -> Result<(), String> {
+        let started = Instant::now();
+        loop {
+            self.app.update();
+            let world = self.app.world();
+            let assets = world.resource::<load_assets::Assets>();
+            let asset_server = world.resource::<AssetServer>();
+            let mut loaded = true;
+            for id in [
+                assets.level.id().untyped(),
+                assets.alien_kinds.id().untyped(),
+                assets.weapons.id().untyped(),
+                assets.animations.id().untyped(),
+                assets.config.id().untyped(),
+            ] {
+                match asset_server.load_state(id) {
+                    LoadState::Loaded => {}
+                    LoadState::Failed(error) => return Err(error.to_string()),
+                    _ => loaded = false,
+                }
+            }
+            if loaded {
+                // Lets the config be applied.
+                self.app.update();
+                return Ok(());
+            }
+            if started.elapsed() > LOAD_TIMEOUT {
+                return Err("the data files didn't load in time".to_string());
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    /// Starts a new game, which plays out the same for the same seed and
+    /// actions.
+    pub fn reset(&mut self, seed: u64) -> Observation {
+        let world = self.app.world_mut();
+        world.insert_resource(GameRng(StdRng::seed_from_u64(seed)));
+        world.insert_resource(AgentAction::default());
+        // Leaving the game first, so it starts over.
+        self.enter(GameStates::Menu);
+        self.enter(GameStates::Game);
+
+        let world = self.app.world_mut();
+        let mut player = world.query_filtered::<(Entity, &Transform), With<Player>>();
+        let (player, transform) = player.single(world);
+        self.player_x = transform.translation.x;
+        world.entity_mut(player).insert(PlayerController::Agent);
+        self.points = world.resource::<Score>().points;
+        self.lives = world.resource::<Lives>().0;
+        self.observe()
+    }
+
+    fn enter(&mut self, state: GameStates) {
+        self.app
+            .world_mut()
+            .resource_mut::<NextState<GameStates>>()
+            .set(state);
+        self.app.update();
+    }
+
+    /// Whether the game is over or about to be.
+    fn is_over(&self) -> bool {
+        let world = self.app.world();
+        *world.resource::<State<GameStates>>().get() != GameStates::Game
+            || matches!(
+                world.resource::<NextState<GameStates>>(),
+                NextState::Pending(_)
+            )
+    }
+
+    /// Plays `action` for `EnvConfig::frames_per_step` frames, or until the
+    /// game is over. Returns what the agent sees afterwards, its reward and
+    /// whether the game is over.
+    pub fn step(&mut self, action: Action) -> (Observation, f32, bool) {
+        self.app.world_mut().insert_resource(AgentAction(action));
+        for _ in 0..self.config.frames_per_step {
+            if self.is_over() {
+                break;
+            }
+            self.app.update();
+        }
+        let world = self.app.world();
+        let points = world.resource::<Score>().points;
+        let lives = world.resource::<Lives>().0;
+        let reward = points.saturating_sub(self.points) as f32
+            - self.config.life_penalty * self.lives.saturating_sub(lives) as f32;
+        self.points = points;
+        self.lives = lives;
+        (self.observe(), reward, self.is_over())
+    }
+
+    fn observe(&mut self) -> Observation {
+        let world = self.app.world_mut();
+        let lives = world.resource::<Lives>().0;
+        let mut bodies = world.query::<(&Transform, &Collider)>();
+        let bodies: Vec<_> = bodies
+            .iter(world)
+            .map(|(transform, collider)| (transform.translation.truncate(), *collider))
+            .collect();
+        match self.config.observation {
+            ObservationKind::Grid { width, height } => {
+                Observation::Grid(rasterize(&bodies, width, height))
+            }
+            ObservationKind::Features => {
+                let mut features = Features {
+                    player_x: self.player_x,
+                    lives,
+                    ..default()
+                };
+                let scale = Vec2::new(2.0 / WORLD_WIDTH, 2.0 / WORLD_HEIGHT);
+                for (position, collider) in bodies {
+                    let position = position * scale;
+                    let memberships = collider.layers.memberships;
+                    if memberships & layer::PLAYER != 0 {
+                        features.player_x = position.x;
+                    } else if memberships & layer::ALIEN != 0 {
+                        features.aliens.push(position);
+                    } else if memberships & layer::PLAYER_BULLET != 0 {
+                        features.player_bullets.push(position);
+                    } else if memberships & layer::ALIEN_BULLET != 0 {
+                        features.alien_bullets.push(position);
+                    } else if memberships & layer::POWER_UP != 0 {
+                        features.power_ups.push(position);
+                    }
+                }
+                self.player_x = features.player_x;
+                Observation::Features(features)
+            }
+        }
+    }
+}
+
+fn rasterize(bodies: &[(Vec2, Collider)], width: usize, height: usize) -> Grid {
+    let mut grid = Grid {
+        width,
+        height,
+        cells: vec![0.0; GRID_LAYERS.len() * width * height],
+    };
+    let cell_size = Vec2::new(WORLD_WIDTH / width as f32, WORLD_HEIGHT / height as f32);
+    for (position, collider) in bodies {
+        let bounds = collider.bounds(*position);
+        // Cells counted from the top left corner of the world.
+        let min = (Vec2::new(bounds.min.x, -bounds.max.y)
+            + Vec2::new(WORLD_WIDTH, WORLD_HEIGHT) / 2.0)
+            / cell_size;
+        let max = (Vec2::new(bounds.max.x, -bounds.min.y)
+            + Vec2::new(WORLD_WIDTH, WORLD_HEIGHT) / 2.0)
+            / cell_size;
+        let columns = (min.x.max(0.0) as usize)..(max.x.ceil().max(0.0) as usize).min(width);
+        let rows = (min.y.max(0.0) as usize)..(max.y.ceil().max(0.0) as usize).min(height);
+        for (index, layer) in GRID_LAYERS.iter().enumerate() {
+            if collider.layers.memberships & layer == 0 {
+                continue;
+            }
+            for y in rows.clone() {
+                for x in columns.clone() {
+                    grid.cells[(index * height + y) * width + x] = 1.0;
+                }
+            }
+        }
+    }
+    grid
+}
+
+/// Moves and fires the ship of a `PlayerController::Agent` as the action
+/// of the current step says.
+fn drive_agent(
+    time: Res<Time>,
+    config: Res<GameConfig>,
+    action: Res<AgentAction>,
+    player: Single<(&mut Transform, &PlayerController), With<Player>>,
+    mut player_shot_event_writer: EventWriter<PlayerShot>,
+) {
+    let (mut transform, controller) = player.into_inner();
+    if *controller != PlayerController::Agent {
+        return;
+    }
+    let y = -WORLD_HEIGHT / 2.0 + config.padding;
+    transform.translation.y = y;
+    transform.translation.x = (transform.translation.x
+        + action.0.movement.clamp(-1.0, 1.0) * config.player_max_speed * time.delta_secs())
+    .clamp(-WORLD_WIDTH / 2.0, WORLD_WIDTH / 2.0);
+    if action.0.fire {
+        player_shot_event_writer.send(PlayerShot {
+            x: transform.translation.x,
+            y: y + PLAYER_HEIGHT / 2.0,
+            auto: true,
+            charge: 0.0,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collision::CollisionLayers;
+
+    fn features(observation: Observation) -> Features {
+        match observation {
+            Observation::Features(features) => features,
+            Observation::Grid(_) => panic!("expected features"),
+        }
+    }
+
+    /// Moves back and forth and shoots every other step.
+    fn scripted_action(step: usize) -> Action {
+        Action {
+            movement: if (step / 30).is_multiple_of(2) {
+                1.0
+            } else {
+                -1.0
+            },
+            fire: step.is_multiple_of(2),
+        }
+    }
+
+    #[test]
+    fn same_seed_and_actions_play_out_the_same() {
+        let config = EnvConfig {
+            observation: ObservationKind::Grid {
+                width: 32,
+                height: 32,
+            },
+            ..default()
+        };
+        let mut a = Environment::new(config.clone()).unwrap();
+        let mut b = Environment::new(config).unwrap();
+        assert_eq!(a.reset(3), b.reset(3));
+        for step in 0..600 {
+            let (observation_a, reward_a, done_a) = a.step(scripted_action(step));
+            let (observation_b, reward_b, done_b) = b.step(scripted_action(step));
+            assert_eq!(observation_a, observation_b, "step {step}");
+            assert_eq!(reward_a, reward_b, "step {step}");
+            assert_eq!(done_a, done_b, "step {step}");
+            if done_a {
+                break;
+            }
+        }
+    }
+
+    #[test]
+    fn reset_starts_a_new_game() {
+        let mut env = Environment::new(EnvConfig::default()).unwrap();
+        let first = features(env.reset(0));
+        assert_eq!(first.lives, Difficulty::default().settings().lives);
+        assert!(!first.aliens.is_empty());
+        assert!(first.player_bullets.is_empty() && first.alien_bullets.is_empty());
+
+        for step in 0..100 {
+            env.step(scripted_action(step));
+        }
+        let second = features(env.reset(0));
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn step_moves_the_ship_and_rewards_shot_down_aliens() {
+        let mut env = Environment::new(EnvConfig::default()).unwrap();
+        let start_x = features(env.reset(0)).player_x;
+        let (observation, _, _) = env.step(Action {
+            movement: 1.0,
+            fire: false,
+        });
+        assert!(features(observation).player_x > start_x);
+
+        let mut shot_down = false;
+        for _ in 0..600 {
+            let (observation, reward, done) = env.step(Action {
+                movement: 0.0,
+                fire: true,
+            });
+            shot_down |= reward > 0.0;
+            if done {
+                break;
+            }
+            assert!(features(observation).player_bullets.len() <= 3);
+        }
+        assert!(shot_down);
+    }
+
+    #[test]
+    fn rasterize_marks_the_cells_a_body_covers() {
+        let alien = Collider::aabb(
+            Vec2::new(8.0, 8.0),
+            CollisionLayers::new(layer::ALIEN, layer::PLAYER_BULLET),
+        );
+        // 8 cells of 32 units each way, the body covers the four cells
+        // around the center of the world.
+        let grid = rasterize(&[(Vec2::ZERO, alien)], 8, 8);
+        let alien_layer = GRID_LAYERS.iter().position(|l| *l == layer::ALIEN).unwrap();
+        for layer in 0..GRID_LAYERS.len() {
+            for y in 0..8 {
+                for x in 0..8 {
+                    let covered =
+                        layer == alien_layer && (3..=4).contains(&x) && (3..=4).contains(&y);
+                    let expected = if covered { 1.0 } else { 0.0 };
+                    assert_eq!(grid.get(layer, x, y), expected, "{layer} {x} {y}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rasterize_counts_rows_from_the_top() {
+        let bullet = Collider::aabb(
+            Vec2::new(1.0, 4.0),
+            CollisionLayers::new(layer::PLAYER_BULLET, layer::ALIEN),
+        );
+        let near_top = Vec2::new(-WORLD_WIDTH / 2.0 + 1.0, WORLD_HEIGHT / 2.0 - 4.0);
+        let grid = rasterize(&[(near_top, bullet)], 4, 4);
+        let bullet_layer = GRID_LAYERS
+            .iter()
+            .position(|l| *l == layer::PLAYER_BULLET)
+            .unwrap();
+        assert_eq!(grid.get(bullet_layer, 0, 0), 1.0);
+        assert_eq!(grid.cells.iter().sum::<f32>(), 1.0);
+    }
+
+    #[test]
+    fn rasterize_leaves_out_bodies_outside_the_world() {
+        let alien = Collider::aabb(
+            Vec2::new(8.0, 8.0),
+            CollisionLayers::new(layer::ALIEN, layer::PLAYER_BULLET),
+        );
+        let grid = rasterize(&[(Vec2::new(WORLD_WIDTH, 0.0), alien)], 8, 8);
+        assert!(grid.cells.iter().all(|cell| *cell == 0.0));
+    }
+}
@@ -1,14 +1,37 @@
 use bevy::prelude::*;
 
-use crate::{despawn_component_type, GameStates, DISPLAY_HEIGHT, WORLD_HEIGHT};
+use bevy::render::view::RenderLayers;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::audio::stereo_listener;
+use crate::{despawn_component_type, GameStates};
 
 pub struct GamePlugin;
 
 #[derive(Component)]
 pub struct OnGameScreen;
 
+/// Randomness deciding how a game plays out: which alien shoots, where it
+/// aims and which power-ups drop. The RL environment seeds it, so a game
+/// can be replayed.
+#[derive(Resource)]
+pub struct GameRng(pub StdRng);
+
+impl Default for GameRng {
+    fn default() -> Self {
+        GameRng(StdRng::from_entropy())
+    }
+}
+
+/// The camera showing the playfield. Its viewport is kept to the part of
+/// the window the playfield is scaled to, see `ScalingPlugin`.
+#[derive(Component)]
+pub struct MainCamera;
+
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
+        app.init_resource::<GameRng>();
         app.add_systems(Startup, setup_camera);
         app.add_systems(
             OnExit(GameStates::Game),
@@ -18,7 +41,15 @@ impl Plugin for GamePlugin {
 }
 
 fn setup_camera(mut commands: Commands) {
-    let mut projection = OrthographicProjection::default_2d();
-    projection.scale = WORLD_HEIGHT / DISPLAY_HEIGHT;
-    commands.spawn((Camera2d, projection));
+    commands.spawn((MainCamera, Camera2d, IsDefaultUiCamera, stereo_listener()));
+    // Draws nothing but the clear color, for the letterbox bars around the
+    // main camera's viewport.
+    commands.spawn((
+        Camera2d,
+        Camera {
+            order: -1,
+            ..default()
+        },
+        RenderLayers::none(),
+    ));
 }
@@ -0,0 +1,185 @@
+use bevy::prelude::*;
+
+use crate::alien::Alien;
+use crate::config::GameConfig;
+use crate::game::OnGameScreen;
+use crate::level::Level;
+use crate::load_assets;
+use crate::player::Lives;
+use crate::powerup::ActivePowerUps;
+use crate::scaling::UI_DESIGN_SCALE;
+use crate::score::{HighScores, Score};
+use crate::{GameStates, TEXT_COLOR};
+
+const HUD_FONT_SIZE: f32 = 16.0;
+/// Space between the texts and the sides of the window, in UI pixels.
+const HUD_MARGIN: f32 = 8.0;
+
+/// Score, high score and level in the padding above the playfield, lives,
+/// running power-ups and the aliens left in the padding below it. Every
+/// text is only rewritten when what it shows changes.
+pub struct HudPlugin;
+
+/// A strip of padding the HUD texts are laid out in.
+#[derive(Component)]
+struct HudStrip;
+
+#[derive(Component)]
+struct ScoreText;
+
+#[derive(Component)]
+struct HighScoreText;
+
+#[derive(Component)]
+struct LevelText;
+
+#[derive(Component)]
+struct LivesText;
+
+#[derive(Component)]
+struct PowerUpText;
+
+#[derive(Component)]
+struct AliensText;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameStates::Game), setup_hud);
+        app.add_systems(
+            Update,
+            (
+                fit_strips_to_padding.run_if(resource_changed::<GameConfig>),
+                update_score.run_if(resource_changed::<Score>),
+                update_level.run_if(on_event::<AssetEvent<Level>>),
+                update_lives.run_if(resource_changed::<Lives>),
+                update_power_ups.run_if(resource_changed::<ActivePowerUps>.or(power_ups_running)),
+                update_aliens,
+            )
+                .run_if(in_state(GameStates::Game)),
+        );
+    }
+}
+
+fn strip_height(config: &GameConfig) -> Val {
+    Val::Px(config.padding * UI_DESIGN_SCALE)
+}
+
+fn setup_hud(
+    mut cmd: Commands,
+    assets: Res<load_assets::Assets>,
+    levels: Res<Assets<Level>>,
+    config: Res<GameConfig>,
+) {
+    let text_font = TextFont {
+        font: assets.orbitron_font.clone(),
+        font_size: HUD_FONT_SIZE,
+        ..default()
+    };
+    let strip = |top: bool| {
+        (
+            HudStrip,
+            OnGameScreen,
+            Node {
+                position_type: PositionType::Absolute,
+                top: if top { Val::Px(0.0) } else { Val::Auto },
+                bottom: if top { Val::Auto } else { Val::Px(0.0) },
+                width: Val::Percent(100.0),
+                height: strip_height(&config),
+                padding: UiRect::horizontal(Val::Px(HUD_MARGIN)),
+                justify_content: JustifyContent::SpaceBetween,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+        )
+    };
+    let text = |content: String| (Text::new(content), TextColor(TEXT_COLOR), text_font.clone());
+
+    let level_name = levels
+        .get(&assets.level)
+        .map(|level| level.name.clone())
+        .unwrap_or_default();
+    cmd.spawn(strip(true)).with_children(|parent| {
+        parent.spawn((ScoreText, text(String::new())));
+        parent.spawn((HighScoreText, text(String::new())));
+        parent.spawn((LevelText, text(level_text(&level_name))));
+    });
+    cmd.spawn(strip(false)).with_children(|parent| {
+        parent.spawn((LivesText, text(String::new())));
+        parent.spawn((PowerUpText, text(String::new())));
+        parent.spawn((AliensText, text(String::new())));
+    });
+}
+
+fn level_text(name: &str) -> String {
+    format!("Level {name}")
+}
+
+fn fit_strips_to_padding(config: Res<GameConfig>, mut strips: Query<&mut Node, With<HudStrip>>) {
+    for mut node in &mut strips {
+        node.height = strip_height(&config);
+    }
+}
+
+fn update_score(
+    score: Res<Score>,
+    high_scores: Res<HighScores>,
+    mut score_text: Single<&mut Text, (With<ScoreText>, Without<HighScoreText>)>,
+    mut high_score_text: Single<&mut Text, With<HighScoreText>>,
+) {
+    score_text.0 = format!("Punkte {}", score.points);
+    // A new record shows up while it is being set.
+    let high_score = high_scores
+        .best(score.difficulty)
+        .unwrap_or(0)
+        .max(score.points);
+    high_score_text.0 = format!("Rekord {high_score}");
+}
+
+fn update_level(
+    assets: Res<load_assets::Assets>,
+    levels: Res<Assets<Level>>,
+    mut level_text_ref: Single<&mut Text, With<LevelText>>,
+) {
+    if let Some(level) = levels.get(&assets.level) {
+        level_text_ref.0 = level_text(&level.name);
+    }
+}
+
+fn update_lives(lives: Res<Lives>, mut lives_text: Single<&mut Text, With<LivesText>>) {
+    lives_text.0 = format!("Leben {}", lives.0);
+}
+
+/// The timers tick without marking `ActivePowerUps` as changed, so the
+/// countdown has to be redrawn every frame while a power-up runs.
+fn power_ups_running(active: Res<ActivePowerUps>) -> bool {
+    active.remaining().next().is_some()
+}
+
+/// The running power-ups with the whole seconds they have left. The text
+/// is only rewritten when one of those changes.
+fn update_power_ups(
+    active: Res<ActivePowerUps>,
+    mut power_up_text: Single<&mut Text, With<PowerUpText>>,
+) {
+    let text = active
+        .remaining()
+        .map(|(kind, seconds)| format!("{} {:.0}s", kind.name(), seconds.ceil()))
+        .collect::<Vec<_>>()
+        .join("  ");
+    if power_up_text.0 != text {
+        power_up_text.0 = text;
+    }
+}
+
+/// Recounts the aliens whenever some were spawned or removed.
+fn update_aliens(
+    aliens: Query<(), With<Alien>>,
+    added: Query<(), Added<Alien>>,
+    mut removed: RemovedComponents<Alien>,
+    mut aliens_text: Single<&mut Text, With<AliensText>>,
+) {
+    if added.is_empty() && removed.read().count() == 0 {
+        return;
+    }
+    aliens_text.0 = format!("Aliens {}", aliens.iter().count());
+}
@@ -0,0 +1,79 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::alien::ALIEN_WIDTH;
+use crate::alien_kind::{AlienKinds, DEFAULT_ALIEN_KIND};
+use crate::config::GameConfig;
+use crate::ron_asset::{register_ron_asset, RonAsset};
+use crate::WORLD_WIDTH;
+
+pub struct LevelPlugin;
+
+/// Layout of an alien wave, loaded from `assets/levels/*.level.ron`.
+#[derive(Asset, TypePath, Debug, Clone, Deserialize)]
+pub struct Level {
+    /// Shown in the HUD.
+    #[serde(default)]
+    pub name: String,
+    /// Alien kind of every row, from the top down. The names refer to the
+    /// kinds in `assets/*.aliens.ron`.
+    pub rows: Vec<String>,
+    pub columns: u32,
+}
+
+impl Default for Level {
+    fn default() -> Self {
+        Level {
+            name: "1".to_string(),
+            rows: vec![DEFAULT_ALIEN_KIND.to_string(); 3],
+            columns: 8,
+        }
+    }
+}
+
+impl Level {
+    pub fn formation_width(&self, config: &GameConfig) -> f32 {
+        self.columns as f32 * ALIEN_WIDTH + self.columns.saturating_sub(1) as f32 * config.alien_gap
+    }
+
+    /// Checks that the formation leaves the aliens room to move sideways.
+    pub fn check_fits(&self, config: &GameConfig) -> Result<(), String> {
+        let formation_width = self.formation_width(config);
+        if formation_width >= WORLD_WIDTH {
+            return Err(format!(
+                "{} columns with an alien_gap of {} are {} wide, leaving no room to move in a world {} wide",
+                self.columns, config.alien_gap, formation_width, WORLD_WIDTH
+            ));
+        }
+        Ok(())
+    }
+
+    /// Checks that every row names a known alien kind.
+    pub fn check_kinds(&self, kinds: &AlienKinds) -> Result<(), String> {
+        match self
+            .rows
+            .iter()
+            .find(|kind| !kinds.kinds.contains_key(*kind))
+        {
+            Some(unknown) => Err(format!("unknown alien kind {unknown:?}")),
+            None => Ok(()),
+        }
+    }
+}
+
+impl RonAsset for Level {
+    const EXTENSIONS: &'static [&'static str] = &["level.ron"];
+
+    fn validate(&self) -> Result<(), String> {
+        if self.rows.is_empty() || self.columns == 0 {
+            return Err("a level needs at least one row and one column".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl Plugin for LevelPlugin {
+    fn build(&self, app: &mut App) {
+        register_ron_asset::<Level>(app);
+    }
+}
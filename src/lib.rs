@@ -0,0 +1,162 @@
+use alien::AlienPlugin;
+use alien_kind::AlienKindPlugin;
+use animation::SpriteAnimationPlugin;
+use asset_errors::AssetErrorsPlugin;
+use attract::AttractModePlugin;
+use audio::AudioManagerPlugin;
+use bevy::prelude::*;
+use bullet::BulletsPlugin;
+use camera_effects::CameraEffectsPlugin;
+use collision::CollisionPlugin;
+use config::{ConfigPlugin, GameConfig};
+use crt::CrtPlugin;
+use detect_win_or_loss::DetectWinOrLossPlugin;
+pub use difficulty::Difficulty;
+use game::GamePlugin;
+use hud::HudPlugin;
+use level::LevelPlugin;
+use load_assets::LoadAssetsPlugin;
+use menu::MenuPlugin;
+use music::MusicPlugin;
+use particles::ParticlesPlugin;
+use pilot::PilotPlugin;
+use player::{PlayerPlugin, PlayerShot};
+use powerup::PowerUpPlugin;
+use scaling::ScalingPlugin;
+use score::ScorePlugin;
+use settings::Settings;
+use sfxr::SfxrPlugin;
+use starfield::StarfieldPlugin;
+use weapon::WeaponPlugin;
+
+const DISPLAY_WIDTH: f32 = 512f32;
+const DISPLAY_HEIGHT: f32 = 512f32;
+
+const WORLD_WIDTH: f32 = 256f32;
+const WORLD_HEIGHT: f32 = 256f32;
+
+mod alien;
+mod alien_kind;
+mod animation;
+mod asset_errors;
+mod attract;
+mod audio;
+mod bullet;
+mod camera_effects;
+pub mod collision;
+mod config;
+mod crt;
+mod detect_win_or_loss;
+mod difficulty;
+pub mod env;
+mod game;
+mod hud;
+mod level;
+mod load_assets;
+mod menu;
+mod music;
+mod particles;
+mod pilot;
+mod player;
+mod powerup;
+mod ron_asset;
+mod scaling;
+mod score;
+mod settings;
+mod sfxr;
+pub mod spatial_grid;
+mod starfield;
+mod start_game_on_button_press;
+mod tune;
+mod weapon;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, States, Default)]
+enum GameStates {
+    #[default]
+    Startup,
+    /// The main menu, the other pages of the menu follow it.
+    Menu,
+    ModeSelect,
+    Settings,
+    HighScores,
+    Credits,
+    Game,
+    End,
+}
+
+/// Opens the game window and runs the game until it is closed.
+pub fn run() {
+    // The window is resized as soon as the config file is loaded.
+    let padding = GameConfig::default().padding;
+    let mut app = App::new();
+    app.add_plugins(
+        DefaultPlugins
+            .set(WindowPlugin {
+                primary_window: Some(Window {
+                    title: "Space Invaders".to_string(),
+                    position: WindowPosition::Centered(MonitorSelection::Primary),
+                    resolution: Vec2::new(
+                        DISPLAY_WIDTH + 2.0 * padding,
+                        DISPLAY_HEIGHT + 2.0 * padding,
+                    )
+                    .into(),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            })
+            .set(ImagePlugin::default_nearest()),
+    );
+    add_game(&mut app);
+    // The march and the menu music, left out of the headless environment.
+    app.add_plugins(MusicPlugin);
+    app.run();
+}
+
+/// The states, resources and plugins of the game, shared by the window and
+/// the headless `env::Environment`.
+fn add_game(app: &mut App) {
+    app.init_state::<GameStates>()
+        .add_event::<PlayerShot>()
+        .init_resource::<Difficulty>()
+        .init_resource::<Settings>()
+        // Data files and the assets loaded from them.
+        .add_plugins((
+            LevelPlugin,
+            AlienKindPlugin,
+            WeaponPlugin,
+            SpriteAnimationPlugin,
+            ConfigPlugin,
+            LoadAssetsPlugin,
+            AssetErrorsPlugin,
+        ))
+        .add_plugins((
+            MenuPlugin,
+            GamePlugin,
+            AlienPlugin,
+            PlayerPlugin,
+            BulletsPlugin,
+            CollisionPlugin,
+            // FrameTimeDiagnosticsPlugin,
+            // LogDiagnosticsPlugin::default(),
+            DetectWinOrLossPlugin,
+            ScorePlugin,
+            PowerUpPlugin,
+            ParticlesPlugin,
+            CameraEffectsPlugin,
+            StarfieldPlugin,
+            ScalingPlugin,
+            CrtPlugin,
+            HudPlugin,
+        ))
+        // The computer playing, instead of or as the player.
+        .add_plugins((AttractModePlugin, PilotPlugin))
+        // Sound effects.
+        .add_plugins((AudioManagerPlugin, SfxrPlugin));
+}
+
+const TEXT_COLOR: Color = Color::srgb(0.8, 0.8, 0.8);
+fn despawn_component_type<T: Component>(to_despawn: Query<Entity, With<T>>, mut cmd: Commands) {
+    for entity in &to_despawn {
+        cmd.entity(entity).despawn_recursive();
+    }
+}
@@ -1,5 +1,11 @@
 use bevy::prelude::*;
 
+use crate::alien_kind::AlienKinds;
+use crate::animation::Animations;
+use crate::config::GameConfig;
+use crate::level::Level;
+use crate::sfxr::SfxrPresets;
+use crate::weapon::Weapons;
 use crate::GameStates;
 
 pub struct LoadAssetsPlugin;
@@ -10,8 +16,6 @@ pub struct Assets {
     pub gamepad: Handle<Image>,
     pub keyboard: Handle<Image>,
 
-    pub alien: Handle<Image>,
-    pub _alien_damaged: Handle<Image>,
     pub bullet: Handle<Image>,
     pub player: Handle<Image>,
 
@@ -20,6 +24,13 @@ pub struct Assets {
     pub win_sound: Handle<AudioSource>,
 
     pub destruction_sound: [Handle<AudioSource>; 5],
+    pub sfx_presets: Handle<SfxrPresets>,
+
+    pub level: Handle<Level>,
+    pub alien_kinds: Handle<AlienKinds>,
+    pub weapons: Handle<Weapons>,
+    pub animations: Handle<Animations>,
+    pub config: Handle<GameConfig>,
 }
 
 impl Plugin for LoadAssetsPlugin {
@@ -33,8 +44,6 @@ fn load_assets(
     mut cmd: Commands,
     mut game_state: ResMut<NextState<GameStates>>,
 ) {
-    let alien = asset_server.load("alien.png");
-    let alien_damaged = asset_server.load("alien_damaged.png");
     let bullet = asset_server.load("bullet.png");
     let player = asset_server.load("player.png");
     let gamepad = asset_server.load("gamepad.png");
@@ -44,12 +53,17 @@ fn load_assets(
     let bullet_sound = asset_server.load("sounds/PhaserShoot.ogg");
     let game_over_sound = asset_server.load("sounds/GameOverSound.ogg");
     let win_sound = asset_server.load("sounds/WinSound.ogg");
+    let sfx_presets = asset_server.load("sounds.sfxr.ron");
+
+    let level = asset_server.load("levels/level1.level.ron");
+    let alien_kinds = asset_server.load("invaders.aliens.ron");
+    let weapons = asset_server.load("player.weapons.ron");
+    let animations = asset_server.load("sprites.animations.ron");
+    let config = asset_server.load("game.config.ron");
 
     let assets = Assets {
         gamepad,
         keyboard,
-        alien,
-        _alien_damaged: alien_damaged,
         bullet,
         player,
         bullet_sound,
@@ -63,8 +77,14 @@ fn load_assets(
             asset_server.load("sounds/DestructionSound4.ogg"),
             asset_server.load("sounds/DestructionSound5.ogg"),
         ],
+        sfx_presets,
+        level,
+        alien_kinds,
+        weapons,
+        animations,
+        config,
     };
 
     cmd.insert_resource(assets);
-    game_state.set(GameStates::Splash);
+    game_state.set(GameStates::Menu);
 }
@@ -0,0 +1,569 @@
+use std::time::Duration;
+
+use bevy::app::AppExit;
+use bevy::prelude::*;
+
+use crate::audio::{Bus, PlaySfxExt, SoundId};
+use crate::difficulty::Difficulty;
+use crate::load_assets;
+use crate::score::HighScores;
+use crate::settings::Settings;
+use crate::{despawn_component_type, GameStates, TEXT_COLOR};
+
+const FOCUS_COLOR: Color = Color::srgb(0.666, 0.831, 0.0);
+const SHOOTING_COLOR: Color = Color::srgb(0.666, 0.831, 0.0);
+const MOVEMENT_COLOR: Color = Color::srgb(1.0, 0.502, 0.502);
+const TRANSPARENT: Color = Color::srgba(0.8, 0.8, 0.8, 0.0);
+const GAMEPAD_CONNET_TEXT: &str = "Schließe ein Gamepad an!";
+const GAMEPAD_FOUND_TEXT: &str = "Gamepad gefunden!";
+/// How far the stick has to be pushed to move the focus.
+const STICK_THRESHOLD: f32 = 0.5;
+const VOLUME_STEP: f32 = 0.1;
+/// High scores listed per difficulty.
+const HIGH_SCORES_SHOWN: usize = 5;
+
+/// The main menu and the pages it leads to. Every page is a state of its
+/// own, navigated with the arrow keys, the D-pad or the left stick.
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MenuFocus>();
+        app.add_systems(OnEnter(GameStates::Menu), setup_main_menu)
+            .add_systems(OnEnter(GameStates::ModeSelect), setup_mode_select)
+            .add_systems(OnEnter(GameStates::Settings), setup_settings)
+            .add_systems(OnEnter(GameStates::HighScores), setup_high_scores)
+            .add_systems(OnEnter(GameStates::Credits), setup_credits);
+        for page in GameStates::MENU_PAGES {
+            app.add_systems(OnExit(page), despawn_component_type::<OnMenuScreen>);
+        }
+        app.add_systems(
+            Update,
+            (
+                navigate_menu,
+                update_entries.run_if(
+                    resource_changed::<MenuFocus>
+                        .or(resource_changed::<Settings>)
+                        .or(resource_changed::<Difficulty>),
+                ),
+            )
+                .chain()
+                .run_if(in_menu),
+        )
+        .add_systems(
+            Update,
+            update_gamepad_text.run_if(in_state(GameStates::ModeSelect)),
+        );
+    }
+}
+
+impl GameStates {
+    const MENU_PAGES: [GameStates; 5] = [
+        GameStates::Menu,
+        GameStates::ModeSelect,
+        GameStates::Settings,
+        GameStates::HighScores,
+        GameStates::Credits,
+    ];
+
+    pub fn is_menu(self) -> bool {
+        GameStates::MENU_PAGES.contains(&self)
+    }
+}
+
+fn in_menu(state: Res<State<GameStates>>) -> bool {
+    state.get().is_menu()
+}
+
+#[derive(Component)]
+pub(crate) struct OnMenuScreen;
+
+/// Index of the entry of the current page that has the focus.
+#[derive(Resource, Default)]
+struct MenuFocus(usize);
+
+/// A line of a menu page which can get the focus.
+#[derive(Component)]
+struct MenuEntry {
+    index: usize,
+    label: &'static str,
+    action: MenuAction,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MenuAction {
+    Open(GameStates),
+    Quit,
+    /// Left and right choose the value, activating picks the next one.
+    Difficulty,
+    FireMode,
+    Toggle(Toggle),
+    Volume(Bus),
+}
+
+/// The settings which are turned on and off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Toggle {
+    ScreenShake,
+    HitStop,
+    Flash,
+    PixelPerfect,
+    Crt,
+    MenuMusic,
+    SynthSounds,
+    Stereo,
+    Autopilot,
+}
+
+impl Toggle {
+    fn is_on(self, settings: &Settings) -> bool {
+        match self {
+            Toggle::ScreenShake => settings.screen_shake,
+            Toggle::HitStop => settings.hit_stop,
+            Toggle::Flash => settings.flash,
+            Toggle::PixelPerfect => settings.pixel_perfect,
+            Toggle::Crt => settings.crt,
+            Toggle::MenuMusic => settings.menu_music,
+            Toggle::SynthSounds => settings.synth_sounds,
+            Toggle::Stereo => settings.stereo,
+            Toggle::Autopilot => settings.autopilot,
+        }
+    }
+
+    fn value(self, settings: &mut Settings) -> &mut bool {
+        match self {
+            Toggle::ScreenShake => &mut settings.screen_shake,
+            Toggle::HitStop => &mut settings.hit_stop,
+            Toggle::Flash => &mut settings.flash,
+            Toggle::PixelPerfect => &mut settings.pixel_perfect,
+            Toggle::Crt => &mut settings.crt,
+            Toggle::MenuMusic => &mut settings.menu_music,
+            Toggle::SynthSounds => &mut settings.synth_sounds,
+            Toggle::Stereo => &mut settings.stereo,
+            Toggle::Autopilot => &mut settings.autopilot,
+        }
+    }
+}
+
+impl MenuEntry {
+    fn text(&self, settings: &Settings, difficulty: Difficulty) -> String {
+        match self.action {
+            MenuAction::Open(_) | MenuAction::Quit => self.label.to_string(),
+            MenuAction::Difficulty => format!("{}: < {} >", self.label, difficulty.name()),
+            MenuAction::FireMode => {
+                format!("{}: < {} >", self.label, settings.fire_mode.name())
+            }
+            MenuAction::Toggle(toggle) => {
+                let on_off = if toggle.is_on(settings) { "an" } else { "aus" };
+                format!("{}: {on_off}", self.label)
+            }
+            MenuAction::Volume(bus) => {
+                format!("{}: {:.0}%", self.label, settings.bus_volume(bus) * 100.0)
+            }
+        }
+    }
+}
+
+/// What the player asked the menu for this frame.
+#[derive(Default)]
+struct MenuInput {
+    up: bool,
+    down: bool,
+    left: bool,
+    right: bool,
+    activate: bool,
+    back: bool,
+}
+
+impl MenuInput {
+    fn read(
+        keyboard_input: &ButtonInput<KeyCode>,
+        gamepads: &Query<&Gamepad>,
+        last_stick: &mut IVec2,
+    ) -> MenuInput {
+        let key = |keys: &[KeyCode]| keyboard_input.any_just_pressed(keys.iter().copied());
+        let button =
+            |button: GamepadButton| gamepads.iter().any(|gamepad| gamepad.just_pressed(button));
+        // The stick counts once per push, like a button.
+        let stick = gamepads
+            .iter()
+            .map(|gamepad| gamepad.left_stick())
+            .find(|stick| stick.abs().max_element() > STICK_THRESHOLD)
+            .map(|stick| {
+                if stick.x.abs() > stick.y.abs() {
+                    IVec2::new(stick.x.signum() as i32, 0)
+                } else {
+                    IVec2::new(0, stick.y.signum() as i32)
+                }
+            })
+            .unwrap_or_default();
+        let pushed = if stick != *last_stick {
+            stick
+        } else {
+            IVec2::ZERO
+        };
+        *last_stick = stick;
+        MenuInput {
+            up: key(&[KeyCode::ArrowUp, KeyCode::KeyW])
+                || button(GamepadButton::DPadUp)
+                || pushed.y > 0,
+            down: key(&[KeyCode::ArrowDown, KeyCode::KeyS])
+                || button(GamepadButton::DPadDown)
+                || pushed.y < 0,
+            left: key(&[KeyCode::ArrowLeft, KeyCode::KeyA])
+                || button(GamepadButton::DPadLeft)
+                || pushed.x < 0,
+            right: key(&[KeyCode::ArrowRight, KeyCode::KeyD])
+                || button(GamepadButton::DPadRight)
+                || pushed.x > 0,
+            activate: key(&[KeyCode::Enter, KeyCode::Space]) || button(GamepadButton::South),
+            back: key(&[KeyCode::Escape, KeyCode::Backspace]) || button(GamepadButton::East),
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn navigate_menu(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    entries: Query<&MenuEntry>,
+    state: Res<State<GameStates>>,
+    mut focus: ResMut<MenuFocus>,
+    mut settings: ResMut<Settings>,
+    mut difficulty: ResMut<Difficulty>,
+    mut next_state: ResMut<NextState<GameStates>>,
+    mut app_exit: EventWriter<AppExit>,
+    mut last_stick: Local<IVec2>,
+    mut cmd: Commands,
+) {
+    let input = MenuInput::read(&keyboard_input, &gamepads, &mut last_stick);
+    if input.back && *state.get() != GameStates::Menu {
+        next_state.set(GameStates::Menu);
+        cmd.play_sfx(SoundId::UiSelect);
+        return;
+    }
+    let count = entries.iter().count();
+    if count == 0 {
+        return;
+    }
+    if input.up || input.down {
+        focus.0 = if input.up {
+            (focus.0 + count - 1) % count
+        } else {
+            (focus.0 + 1) % count
+        };
+        cmd.play_sfx(SoundId::UiSelect);
+        return;
+    }
+    let Some(entry) = entries.iter().find(|entry| entry.index == focus.0) else {
+        return;
+    };
+    // -1 or 1 for values chosen with left and right.
+    let step = match (input.left, input.right || input.activate) {
+        (true, _) => -1,
+        (_, true) => 1,
+        _ => return,
+    };
+    match entry.action {
+        MenuAction::Open(page) if input.activate => next_state.set(page),
+        MenuAction::Quit if input.activate => {
+            app_exit.send(AppExit::Success);
+        }
+        MenuAction::Open(_) | MenuAction::Quit => return,
+        MenuAction::Difficulty => {
+            *difficulty = if step > 0 {
+                difficulty.next()
+            } else {
+                difficulty.previous()
+            };
+        }
+        MenuAction::FireMode => {
+            settings.fire_mode = if step > 0 {
+                settings.fire_mode.next()
+            } else {
+                settings.fire_mode.previous()
+            };
+        }
+        MenuAction::Toggle(toggle) => {
+            let value = toggle.value(&mut settings);
+            *value = !*value;
+        }
+        MenuAction::Volume(bus) => {
+            let value = settings.bus_volume_mut(bus);
+            // Rounded, so the steps stay on whole percents.
+            *value = ((*value + step as f32 * VOLUME_STEP).clamp(0.0, 1.0) * 100.0).round() / 100.0;
+        }
+    }
+    cmd.play_sfx(SoundId::UiSelect);
+}
+
+fn update_entries(
+    focus: Res<MenuFocus>,
+    settings: Res<Settings>,
+    difficulty: Res<Difficulty>,
+    mut entries: Query<(&MenuEntry, &mut Text, &mut TextColor)>,
+) {
+    for (entry, mut text, mut color) in &mut entries {
+        text.0 = entry.text(&settings, *difficulty);
+        color.0 = if entry.index == focus.0 {
+            FOCUS_COLOR
+        } else {
+            TEXT_COLOR
+        };
+    }
+}
+
+/// Spawns a page with a title, whatever `content` adds below it and the
+/// entries at the bottom.
+fn spawn_page(
+    cmd: &mut Commands,
+    assets: &load_assets::Assets,
+    title: &str,
+    entries: &[(&'static str, MenuAction)],
+    content: impl FnOnce(&mut ChildBuilder),
+) {
+    cmd.insert_resource(MenuFocus(0));
+    cmd.spawn((
+        OnMenuScreen,
+        Node {
+            row_gap: Val::Px(8.0),
+            flex_direction: FlexDirection::Column,
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+    ))
+    .with_children(|parent| {
+        parent.spawn((
+            Text::new(title),
+            TextColor(TEXT_COLOR),
+            TextFont {
+                font: assets.orbitron_font.clone(),
+                font_size: 32.0,
+                ..default()
+            },
+            Node {
+                margin: UiRect::bottom(Val::Px(16.0)),
+                ..default()
+            },
+        ));
+        content(parent);
+        for (index, (label, action)) in entries.iter().enumerate() {
+            parent.spawn((
+                MenuEntry {
+                    index,
+                    label,
+                    action: *action,
+                },
+                // Filled in by `update_entries`.
+                Text::new(""),
+                TextColor(TEXT_COLOR),
+                TextFont {
+                    font: assets.orbitron_font.clone(),
+                    font_size: 20.0,
+                    ..default()
+                },
+            ));
+        }
+    });
+}
+
+fn small_text(assets: &load_assets::Assets, text: impl Into<String>, color: Color) -> impl Bundle {
+    (
+        Text::new(text),
+        TextLayout::new_with_justify(JustifyText::Center),
+        TextColor(color),
+        TextFont {
+            font: assets.orbitron_font.clone(),
+            font_size: 16.0,
+            ..default()
+        },
+    )
+}
+
+fn setup_main_menu(mut cmd: Commands, assets: Res<load_assets::Assets>) {
+    spawn_page(
+        &mut cmd,
+        &assets,
+        "Space Invaders",
+        &[
+            ("Start", MenuAction::Open(GameStates::Game)),
+            ("Modus", MenuAction::Open(GameStates::ModeSelect)),
+            ("Einstellungen", MenuAction::Open(GameStates::Settings)),
+            ("Bestenliste", MenuAction::Open(GameStates::HighScores)),
+            ("Credits", MenuAction::Open(GameStates::Credits)),
+            ("Beenden", MenuAction::Quit),
+        ],
+        |_| {},
+    );
+}
+
+#[derive(Component)]
+struct GamepadWaitText {
+    timer: Timer,
+}
+
+/// Difficulty and fire mode, along with the controls.
+fn setup_mode_select(mut cmd: Commands, assets: Res<load_assets::Assets>) {
+    spawn_page(
+        &mut cmd,
+        &assets,
+        "Modus",
+        &[
+            ("Schwierigkeit", MenuAction::Difficulty),
+            ("Feuermodus", MenuAction::FireMode),
+            ("Start", MenuAction::Open(GameStates::Game)),
+            ("Zurück", MenuAction::Open(GameStates::Menu)),
+        ],
+        |parent| {
+            parent.spawn((
+                ImageNode::new(assets.gamepad.clone()),
+                Node {
+                    width: Val::Px(120.0),
+                    ..default()
+                },
+            ));
+            parent.spawn((
+                ImageNode::new(assets.keyboard.clone()),
+                Node {
+                    width: Val::Px(240.0),
+                    ..default()
+                },
+            ));
+            parent
+                .spawn(Node {
+                    flex_direction: FlexDirection::Row,
+                    column_gap: Val::Px(64.0),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn(small_text(&assets, "Bewegen", MOVEMENT_COLOR));
+                    parent.spawn(small_text(&assets, "Schießen", SHOOTING_COLOR));
+                });
+            parent.spawn((
+                GamepadWaitText {
+                    timer: Timer::new(Duration::from_secs(2), TimerMode::Repeating),
+                },
+                small_text(&assets, GAMEPAD_CONNET_TEXT, TRANSPARENT),
+                Node {
+                    margin: UiRect::bottom(Val::Px(16.0)),
+                    ..default()
+                },
+            ));
+        },
+    );
+}
+
+fn setup_settings(mut cmd: Commands, assets: Res<load_assets::Assets>) {
+    spawn_page(
+        &mut cmd,
+        &assets,
+        "Einstellungen",
+        &[
+            ("Wackeln", MenuAction::Toggle(Toggle::ScreenShake)),
+            ("Stopp bei Treffern", MenuAction::Toggle(Toggle::HitStop)),
+            ("Blitz", MenuAction::Toggle(Toggle::Flash)),
+            (
+                "Ganzzahlig skalieren",
+                MenuAction::Toggle(Toggle::PixelPerfect),
+            ),
+            ("Röhre", MenuAction::Toggle(Toggle::Crt)),
+            ("Menümusik", MenuAction::Toggle(Toggle::MenuMusic)),
+            ("Synthesizer", MenuAction::Toggle(Toggle::SynthSounds)),
+            ("Stereo", MenuAction::Toggle(Toggle::Stereo)),
+            ("Autopilot", MenuAction::Toggle(Toggle::Autopilot)),
+            ("Effekte", MenuAction::Volume(Bus::Sfx)),
+            ("Musik", MenuAction::Volume(Bus::Music)),
+            ("Menü", MenuAction::Volume(Bus::Ui)),
+            ("Zurück", MenuAction::Open(GameStates::Menu)),
+        ],
+        |_| {},
+    );
+}
+
+fn setup_high_scores(
+    mut cmd: Commands,
+    assets: Res<load_assets::Assets>,
+    high_scores: Res<HighScores>,
+) {
+    spawn_page(
+        &mut cmd,
+        &assets,
+        "Bestenliste",
+        &[("Zurück", MenuAction::Open(GameStates::Menu))],
+        |parent| {
+            for difficulty in Difficulty::ALL {
+                let scores = high_scores.scores(difficulty);
+                let scores = if scores.is_empty() {
+                    "-".to_string()
+                } else {
+                    scores
+                        .iter()
+                        .take(HIGH_SCORES_SHOWN)
+                        .map(u32::to_string)
+                        .collect::<Vec<_>>()
+                        .join("  ")
+                };
+                parent.spawn(small_text(
+                    &assets,
+                    format!("{}: {scores}", difficulty.name()),
+                    TEXT_COLOR,
+                ));
+            }
+            parent.spawn(Node {
+                height: Val::Px(16.0),
+                ..default()
+            });
+        },
+    );
+}
+
+fn setup_credits(mut cmd: Commands, assets: Res<load_assets::Assets>) {
+    spawn_page(
+        &mut cmd,
+        &assets,
+        "Credits",
+        &[("Zurück", MenuAction::Open(GameStates::Menu))],
+        |parent| {
+            parent.spawn((
+                small_text(
+                    &assets,
+                    "Inspiriert von Biped-Potatos Video\n\
+                     Code unter MIT-Lizenz\n\
+                     Alien- und Schussgrafik von Biped-Potato (MIT)\n\
+                     Übrige Grafiken und Sounds von Eric Wolf (CC BY-NC-ND 4.0)\n\
+                     Schrift: Orbitron (SIL Open Font License)",
+                    TEXT_COLOR,
+                ),
+                Node {
+                    margin: UiRect::bottom(Val::Px(16.0)),
+                    ..default()
+                },
+            ));
+        },
+    );
+}
+
+fn update_gamepad_text(
+    gamepads: Query<&Gamepad>,
+    mut gamepad_waittext: Single<(&mut GamepadWaitText, &mut Text, &mut TextColor)>,
+    time: Res<Time>,
+) {
+    let gamepad_present = !gamepads.is_empty();
+    let elapsed = gamepad_waittext.0.timer.tick(time.delta()).elapsed_secs();
+
+    let alpha = if gamepad_present {
+        1.0
+    } else {
+        (2.0 - 2.0 * (elapsed - 1.0) * (elapsed - 1.0)).min(1.0)
+    };
+    let text_ref = &mut *gamepad_waittext.1;
+    if gamepad_present && text_ref.as_str() != GAMEPAD_FOUND_TEXT {
+        *text_ref = Text::new(GAMEPAD_FOUND_TEXT);
+    } else if !gamepad_present && text_ref.as_str() != GAMEPAD_CONNET_TEXT {
+        *text_ref = Text::new(GAMEPAD_CONNET_TEXT);
+    }
+    gamepad_waittext.2.set_alpha(alpha);
+}
@@ -0,0 +1,225 @@
+use bevy::audio::{AddAudioSource, Volume};
+use bevy::prelude::*;
+
+use crate::alien::{Alien, FormationClock};
+use crate::audio::Bus;
+use crate::config::GameConfig;
+use crate::settings::Settings;
+use crate::tune::{Note, Tune, Waveform};
+use crate::GameStates;
+
+/// The descending bass notes of the march (G, F, E, D).
+const MARCH_NOTES: [f32; 4] = [98.0, 87.31, 82.41, 73.42];
+/// Steps of the formation per note of the march, while the whole wave is
+/// alive and once only the last alien is left. With the default alien speed
+/// that's a note every second, speeding up to four a second.
+const MARCH_STEPS_PER_NOTE_FULL: f32 = 16.0;
+const MARCH_STEPS_PER_NOTE_LAST: f32 = 4.0;
+const MARCH_NOTE_DURATION: f32 = 0.12;
+const MARCH_VOLUME: f32 = 0.8;
+const MENU_VOLUME: f32 = 0.3;
+
+/// Seconds of one note of the menu music.
+const BEAT: f32 = 0.3;
+/// Arpeggios over A minor, F, C and G major, looped in the menus.
+const MENU_NOTES: [Note; 24] = [
+    Note::new(220.0, BEAT),
+    Note::new(261.63, BEAT),
+    Note::new(329.63, BEAT),
+    Note::new(440.0, BEAT),
+    Note::new(329.63, BEAT),
+    Note::new(261.63, BEAT),
+    Note::new(174.61, BEAT),
+    Note::new(220.0, BEAT),
+    Note::new(261.63, BEAT),
+    Note::new(349.23, BEAT),
+    Note::new(261.63, BEAT),
+    Note::new(220.0, BEAT),
+    Note::new(261.63, BEAT),
+    Note::new(329.63, BEAT),
+    Note::new(392.0, BEAT),
+    Note::new(523.25, BEAT),
+    Note::new(392.0, BEAT),
+    Note::new(329.63, BEAT),
+    Note::new(196.0, BEAT),
+    Note::new(246.94, BEAT),
+    Note::new(293.66, BEAT),
+    Note::new(392.0, BEAT),
+    Note::new(293.66, BEAT),
+    Note::new(246.94, BEAT),
+];
+
+/// The march of the formation during the game and the music of the menus,
+/// which fade into each other when the state changes.
+pub struct MusicPlugin;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Track {
+    Menu,
+    March,
+}
+
+impl Track {
+    /// The track playing in `state`, if any.
+    fn of_state(state: GameStates, settings: &Settings) -> Option<Track> {
+        match state {
+            state if state.is_menu() && settings.menu_music => Some(Track::Menu),
+            GameStates::Game => Some(Track::March),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Resource)]
+struct Tunes {
+    march: [Handle<Tune>; 4],
+    menu: Handle<Tune>,
+}
+
+/// Volume of each track, from 0 to 1. Moves towards 1 for the track of the
+/// current state and towards 0 for the other one.
+#[derive(Resource, Default)]
+struct TrackGains {
+    menu: f32,
+    march: f32,
+}
+
+#[derive(Component)]
+struct MenuMusic;
+
+#[derive(Component)]
+struct MarchNote;
+
+/// How far the march got, reset with every game.
+#[derive(Resource, Default)]
+struct March {
+    /// Formation step of the last note played.
+    last_note_step: Option<u64>,
+    notes_played: usize,
+    /// Most aliens alive at once, to tell how far the formation thinned.
+    wave_size: usize,
+}
+
+impl Plugin for MusicPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_audio_source::<Tune>();
+        app.init_resource::<TrackGains>();
+        app.init_resource::<March>();
+        app.add_systems(Startup, create_tunes);
+        app.add_systems(OnEnter(GameStates::Game), reset_march);
+        app.add_systems(
+            Update,
+            (
+                crossfade,
+                play_menu_music,
+                play_march.run_if(in_state(GameStates::Game)),
+            )
+                .chain(),
+        );
+    }
+}
+
+fn create_tunes(mut tunes: ResMut<Assets<Tune>>, mut commands: Commands) {
+    let march = MARCH_NOTES.map(|frequency| {
+        tunes.add(Tune::new(
+            Waveform::Square,
+            &[Note::new(frequency, MARCH_NOTE_DURATION)],
+        ))
+    });
+    let menu = tunes.add(Tune::new(Waveform::Triangle, &MENU_NOTES));
+    commands.insert_resource(Tunes { march, menu });
+}
+
+fn crossfade(
+    time: Res<Time<Real>>,
+    state: Res<State<GameStates>>,
+    settings: Res<Settings>,
+    config: Res<GameConfig>,
+    mut gains: ResMut<TrackGains>,
+) {
+    let track = Track::of_state(*state.get(), &settings);
+    let step = if config.music_crossfade > 0.0 {
+        time.delta_secs() / config.music_crossfade
+    } else {
+        1.0
+    };
+    let fade = |gain: &mut f32, playing: bool| {
+        let target = if playing { 1.0 } else { 0.0 };
+        *gain += (target - *gain).clamp(-step, step);
+    };
+    fade(&mut gains.menu, track == Some(Track::Menu));
+    fade(&mut gains.march, track == Some(Track::March));
+}
+
+/// Starts the menu music when it fades in, follows its volume and stops it
+/// once it faded out.
+fn play_menu_music(
+    gains: Res<TrackGains>,
+    settings: Res<Settings>,
+    tunes: Res<Tunes>,
+    music: Query<(Entity, Option<&AudioSink>), With<MenuMusic>>,
+    mut commands: Commands,
+) {
+    let volume = MENU_VOLUME * gains.menu * settings.bus_volume(Bus::Music);
+    match music.get_single().ok() {
+        Some((entity, _)) if gains.menu == 0.0 => {
+            commands.entity(entity).despawn();
+        }
+        Some((_, Some(sink))) => sink.set_volume(volume),
+        // Still being started.
+        Some((_, None)) => {}
+        None if gains.menu > 0.0 => {
+            commands.spawn((
+                MenuMusic,
+                AudioPlayer(tunes.menu.clone()),
+                PlaybackSettings::LOOP.with_volume(Volume::new(volume)),
+            ));
+        }
+        None => {}
+    }
+}
+
+fn reset_march(mut march: ResMut<March>) {
+    *march = March::default();
+}
+
+/// Plays the next note of the march every few steps of the formation, so
+/// the music speeds up and slows down along with the aliens. The fewer
+/// aliens are left, the fewer steps there are between two notes.
+#[allow(clippy::too_many_arguments)]
+fn play_march(
+    clock: Res<FormationClock>,
+    gains: Res<TrackGains>,
+    settings: Res<Settings>,
+    tunes: Res<Tunes>,
+    aliens: Query<(), With<Alien>>,
+    playing: Query<Entity, With<MarchNote>>,
+    mut march: ResMut<March>,
+    mut commands: Commands,
+) {
+    let alive = aliens.iter().count();
+    march.wave_size = march.wave_size.max(alive);
+    let thinned = 1.0 - alive as f32 / march.wave_size.max(1) as f32;
+    let steps_per_note = MARCH_STEPS_PER_NOTE_FULL
+        .lerp(MARCH_STEPS_PER_NOTE_LAST, thinned)
+        .round() as u64;
+    let step = clock.march_step();
+    if march
+        .last_note_step
+        .is_some_and(|last| step < last + steps_per_note)
+    {
+        return;
+    }
+    march.last_note_step = Some(step);
+    // A single note at a time, even if the formation races along.
+    for note in &playing {
+        commands.entity(note).despawn();
+    }
+    let volume = MARCH_VOLUME * gains.march * settings.bus_volume(Bus::Music);
+    commands.spawn((
+        MarchNote,
+        AudioPlayer(tunes.march[march.notes_played % tunes.march.len()].clone()),
+        PlaybackSettings::DESPAWN.with_volume(Volume::new(volume)),
+    ));
+    march.notes_played += 1;
+}
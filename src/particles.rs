@@ -0,0 +1,227 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::alien::AlienKilled;
+use crate::game::OnGameScreen;
+use crate::player::PlayerHit;
+use crate::GameStates;
+
+/// Particles alive at once. Emitters skip particles above it, so a busy
+/// moment costs some sparks instead of frames.
+const MAX_PARTICLES: usize = 1500;
+
+pub struct ParticlesPlugin;
+
+/// What the particles of an emitter look like and how they move.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParticleEffect {
+    /// Particles emitted at once by `EmitParticles`.
+    pub burst: u32,
+    /// Particles per second emitted by a `ParticleEmitter`.
+    pub rate: f32,
+    /// Seconds a particle lives, chosen at random in this range.
+    pub lifetime: (f32, f32),
+    /// World units per second, chosen at random in this range.
+    pub speed: (f32, f32),
+    /// Mean direction of the particles in degrees, counter clockwise from +x.
+    pub direction: f32,
+    /// The direction varies by up to half of this in both directions.
+    pub spread: f32,
+    /// Acceleration along -y, in world units per second squared.
+    pub gravity: f32,
+    pub size: f32,
+    pub start_color: Color,
+    pub end_color: Color,
+}
+
+impl ParticleEffect {
+    pub const EXPLOSION: ParticleEffect = ParticleEffect {
+        burst: 24,
+        rate: 0.0,
+        lifetime: (0.3, 0.7),
+        speed: (20.0, 60.0),
+        direction: 0.0,
+        spread: 360.0,
+        gravity: 40.0,
+        size: 1.0,
+        start_color: Color::srgb(1.0, 0.9, 0.5),
+        end_color: Color::srgba(0.9, 0.2, 0.1, 0.0),
+    };
+
+    /// Sparks of a bullet hitting something which took the hit.
+    pub const IMPACT: ParticleEffect = ParticleEffect {
+        burst: 8,
+        lifetime: (0.1, 0.25),
+        speed: (30.0, 60.0),
+        direction: -90.0,
+        spread: 120.0,
+        gravity: 0.0,
+        start_color: Color::WHITE,
+        end_color: Color::srgba(1.0, 0.8, 0.3, 0.0),
+        ..ParticleEffect::EXPLOSION
+    };
+
+    /// Pieces of the player's ship coming off when it is hit.
+    pub const DEBRIS: ParticleEffect = ParticleEffect {
+        burst: 16,
+        lifetime: (0.5, 1.0),
+        speed: (15.0, 45.0),
+        direction: 90.0,
+        spread: 150.0,
+        gravity: 80.0,
+        size: 1.5,
+        start_color: Color::srgb(0.8, 0.8, 0.9),
+        end_color: Color::srgba(0.4, 0.4, 0.5, 0.0),
+        ..ParticleEffect::EXPLOSION
+    };
+
+    /// Left behind by bullets, which move on while the particles linger.
+    pub const TRAIL: ParticleEffect = ParticleEffect {
+        burst: 0,
+        rate: 60.0,
+        lifetime: (0.1, 0.2),
+        speed: (0.0, 4.0),
+        direction: 0.0,
+        spread: 360.0,
+        gravity: 0.0,
+        size: 1.0,
+        start_color: Color::srgba(1.0, 1.0, 1.0, 0.6),
+        end_color: Color::srgba(0.5, 0.5, 1.0, 0.0),
+    };
+
+    /// A single particle at `position`.
+    fn particle(&self, position: Vec2, rng: &mut impl Rng) -> impl Bundle {
+        let angle = (self.direction + rng.gen_range(-0.5..=0.5) * self.spread).to_radians();
+        let speed = rng.gen_range(self.speed.0..=self.speed.1);
+        (
+            Particle {
+                velocity: Vec2::from_angle(angle) * speed,
+                age: 0.0,
+                lifetime: rng.gen_range(self.lifetime.0..=self.lifetime.1),
+                gravity: self.gravity,
+                start_color: self.start_color,
+                end_color: self.end_color,
+            },
+            OnGameScreen,
+            Sprite::from_color(self.start_color, Vec2::splat(self.size)),
+            Transform::from_translation(position.extend(19.0)),
+        )
+    }
+}
+
+/// Emits a burst of particles once.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct EmitParticles {
+    pub effect: ParticleEffect,
+    pub position: Vec2,
+}
+
+/// Emits particles continuously at the entity's position.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct ParticleEmitter {
+    pub effect: ParticleEffect,
+    /// Particles owed from previous frames, emitted once there is a whole one.
+    pending: f32,
+}
+
+impl ParticleEmitter {
+    pub fn new(effect: ParticleEffect) -> Self {
+        ParticleEmitter {
+            effect,
+            pending: 0.0,
+        }
+    }
+}
+
+#[derive(Component, Debug)]
+struct Particle {
+    velocity: Vec2,
+    age: f32,
+    lifetime: f32,
+    gravity: f32,
+    start_color: Color,
+    end_color: Color,
+}
+
+impl Plugin for ParticlesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<EmitParticles>();
+        app.add_systems(
+            Update,
+            (burst_on_hits, emit_particles, update_particles)
+                .chain()
+                .run_if(in_state(GameStates::Game)),
+        );
+    }
+}
+
+fn burst_on_hits(
+    mut alien_killed: EventReader<AlienKilled>,
+    mut player_hit: EventReader<PlayerHit>,
+    mut emit: EventWriter<EmitParticles>,
+) {
+    for killed in alien_killed.read() {
+        emit.send(EmitParticles {
+            effect: ParticleEffect::EXPLOSION,
+            position: killed.position,
+        });
+    }
+    for hit in player_hit.read() {
+        emit.send(EmitParticles {
+            effect: ParticleEffect::DEBRIS,
+            position: hit.position,
+        });
+    }
+}
+
+fn emit_particles(
+    time: Res<Time>,
+    mut bursts: EventReader<EmitParticles>,
+    mut emitters: Query<(&mut ParticleEmitter, &Transform)>,
+    particles: Query<(), With<Particle>>,
+    mut commands: Commands,
+) {
+    let mut rng = rand::thread_rng();
+    let mut budget = MAX_PARTICLES.saturating_sub(particles.iter().count());
+    for burst in bursts.read() {
+        let count = (burst.effect.burst as usize).min(budget);
+        budget -= count;
+        for _ in 0..count {
+            commands.spawn(burst.effect.particle(burst.position, &mut rng));
+        }
+    }
+    for (mut emitter, transform) in &mut emitters {
+        emitter.pending += emitter.effect.rate * time.delta_secs();
+        let count = (emitter.pending as usize).min(budget);
+        // Whatever doesn't fit into the budget is dropped, not saved up.
+        emitter.pending = emitter.pending.fract();
+        budget -= count;
+        for _ in 0..count {
+            commands.spawn(
+                emitter
+                    .effect
+                    .particle(transform.translation.truncate(), &mut rng),
+            );
+        }
+    }
+}
+
+fn update_particles(
+    time: Res<Time>,
+    mut particles: Query<(Entity, &mut Particle, &mut Transform, &mut Sprite)>,
+    mut commands: Commands,
+) {
+    let delta = time.delta_secs();
+    for (entity, mut particle, mut transform, mut sprite) in &mut particles {
+        particle.age += delta;
+        if particle.age >= particle.lifetime {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        particle.velocity.y -= particle.gravity * delta;
+        transform.translation += (particle.velocity * delta).extend(0.0);
+        sprite.color = particle
+            .start_color
+            .mix(&particle.end_color, particle.age / particle.lifetime);
+    }
+}
@@ -0,0 +1,155 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::alien::{Alien, AlienMovementCurve, FormationClock, ALIEN_WIDTH};
+use crate::bullet::{AlienBullet, Velocity};
+use crate::config::GameConfig;
+use crate::difficulty::Difficulty;
+use crate::game::GameRng;
+use crate::player::{Player, PlayerController, PlayerShot, PLAYER_HEIGHT, PLAYER_WIDTH};
+use crate::{GameStates, WORLD_HEIGHT, WORLD_WIDTH};
+
+/// Alien shots hitting the ship sooner than this, in seconds, are dodged.
+const DODGE_TIME: f32 = 0.6;
+/// The pilot shoots once it is this close to where it aims, in world units.
+const FIRE_TOLERANCE: f32 = ALIEN_WIDTH / 4.0;
+
+/// Flies the player's ship when its `PlayerController` is an `AiPilot`.
+/// The pilot dodges alien shots about to hit and shoots at where the
+/// formation will have taken an alien by the time the bullet gets there.
+pub struct PilotPlugin;
+
+/// An AI pilot, how well it plays and what it is up to. Every ship starts
+/// with a pilot of its own, which has yet to make its first decision.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AiPilot {
+    /// Seconds between two decisions: alien shots fired in between are
+    /// noticed late, and a shot down target is replaced late.
+    pub reaction_time: f32,
+    /// Shots miss the target by up to this many world units, picked anew
+    /// with every decision.
+    pub aim_error: f32,
+    decision: Decision,
+}
+
+impl AiPilot {
+    pub fn new(reaction_time: f32, aim_error: f32) -> AiPilot {
+        AiPilot {
+            reaction_time,
+            aim_error,
+            decision: Decision::default(),
+        }
+    }
+}
+
+/// What the pilot decided on last.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Decision {
+    /// Seconds until the next decision.
+    next_in: f32,
+    target: Option<Entity>,
+    aim_offset: f32,
+    /// Where the ship moves to get out of the way of an alien shot.
+    dodge_x: Option<f32>,
+}
+
+impl Plugin for PilotPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, fly_pilot.run_if(in_state(GameStates::Game)));
+    }
+}
+
+/// Where the next alien shot crosses the height of the ship at `x`, if it is
+/// going to hit within `DODGE_TIME`.
+fn threat(
+    alien_bullets: &Query<(&GlobalTransform, &Velocity), With<AlienBullet>>,
+    x: f32,
+    y: f32,
+) -> Option<f32> {
+    alien_bullets
+        .iter()
+        .filter(|(_, velocity)| velocity.0.y < 0.0)
+        .map(|(transform, velocity)| {
+            let seconds = (transform.translation().y - y) / -velocity.0.y;
+            (seconds, transform.translation().x + velocity.0.x * seconds)
+        })
+        .filter(|&(seconds, hit_x)| {
+            (0.0..DODGE_TIME).contains(&seconds) && (hit_x - x).abs() < PLAYER_WIDTH
+        })
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, hit_x)| hit_x)
+}
+
+/// A spot next to `hit_x`, away from `x` if there is room on that side.
+fn dodge(hit_x: f32, x: f32) -> f32 {
+    let away = if hit_x > x { -1.0 } else { 1.0 };
+    let dodge_x = hit_x + away * PLAYER_WIDTH * 1.5;
+    if dodge_x.abs() > WORLD_WIDTH / 2.0 {
+        hit_x - away * PLAYER_WIDTH * 1.5
+    } else {
+        dodge_x
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn fly_pilot(
+    time: Res<Time>,
+    config: Res<GameConfig>,
+    difficulty: Res<Difficulty>,
+    clock: Res<FormationClock>,
+    movement_curve: Res<AlienMovementCurve>,
+    mut rng: ResMut<GameRng>,
+    player: Single<(&mut Transform, &mut PlayerController), With<Player>>,
+    aliens: Query<(Entity, &Alien)>,
+    alien_bullets: Query<(&GlobalTransform, &Velocity), With<AlienBullet>>,
+    mut player_shot_event_writer: EventWriter<PlayerShot>,
+) {
+    let (mut transform, mut controller) = player.into_inner();
+    let PlayerController::Ai(pilot) = &mut *controller else {
+        return;
+    };
+    let (reaction_time, aim_error) = (pilot.reaction_time, pilot.aim_error);
+    let decision = &mut pilot.decision;
+    let y = -WORLD_HEIGHT / 2.0 + config.padding;
+    let shot_y = y + PLAYER_HEIGHT / 2.0;
+    let x = transform.translation.x;
+    transform.translation.y = y;
+
+    // Where an alien will be when a shot fired now reaches its height.
+    let bullet_speed = config.bullet_speed * difficulty.settings().bullet_speed;
+    let aim_at = |alien: &Alien| {
+        let now = movement_curve.position(alien, clock.elapsed());
+        let flight_time = (now.y - shot_y).max(0.0) / bullet_speed;
+        movement_curve.position(alien, clock.elapsed() + flight_time * clock.speed)
+    };
+
+    decision.next_in -= time.delta_secs();
+    if decision.next_in <= 0.0 {
+        decision.next_in = reaction_time;
+        decision.dodge_x = threat(&alien_bullets, x, y).map(|hit_x| dodge(hit_x, x));
+        decision.target = aliens
+            .iter()
+            .min_by(|(_, a), (_, b)| (aim_at(a).x - x).abs().total_cmp(&(aim_at(b).x - x).abs()))
+            .map(|(entity, _)| entity);
+        decision.aim_offset = aim_error * rng.0.gen_range(-1.0..=1.0);
+    }
+
+    let aim_x = decision
+        .target
+        .and_then(|target| aliens.get(target).ok())
+        .map(|(_, alien)| aim_at(alien).x + decision.aim_offset);
+    let goal = decision.dodge_x.or(aim_x).unwrap_or(x);
+    let step = config.player_max_speed * time.delta_secs();
+    transform.translation.x =
+        (x + (goal - x).clamp(-step, step)).clamp(-WORLD_WIDTH / 2.0, WORLD_WIDTH / 2.0);
+
+    if aim_x.is_some_and(|aim_x| (aim_x - transform.translation.x).abs() < FIRE_TOLERANCE) {
+        // Sent like autofire, so a blocked shot stays silent.
+        player_shot_event_writer.send(PlayerShot {
+            x: transform.translation.x,
+            y: shot_y,
+            auto: true,
+            charge: 0.0,
+        });
+    }
+}
@@ -1,54 +1,168 @@
 use bevy::prelude::*;
 
-use crate::{game::OnGameScreen, load_assets, GameStates, PADDING, WORLD_HEIGHT, WORLD_WIDTH};
+use crate::{
+    animation::SpriteAnimations,
+    attract::AttractMode,
+    collision::{layer, Collider, CollisionLayers},
+    config::GameConfig,
+    difficulty::Difficulty,
+    game::OnGameScreen,
+    load_assets,
+    pilot::AiPilot,
+    settings::{FireMode, Settings},
+    weapon::{Weapon, WeaponCooldown},
+    GameStates, WORLD_HEIGHT, WORLD_WIDTH,
+};
 
 pub struct PlayerPlugin;
 
-const PLAYER_MAX_SPEED: f32 = 128f32;
 const PLAYER_IMAGE_HEIGHT: f32 = 256f32;
 const PLAYER_IMAGE_WIDTH: f32 = 210f32;
 
 pub const PLAYER_HEIGHT: f32 = 16f32;
 pub const PLAYER_WIDTH: f32 = PLAYER_IMAGE_WIDTH * PLAYER_HEIGHT / PLAYER_IMAGE_HEIGHT;
 
+/// Seconds the fire button has to be held for a fully charged shot.
+const FULL_CHARGE_TIME: f32 = 1.0;
+const CHARGE_BAR_HEIGHT: f32 = 2f32;
+const CHARGE_BAR_COLOR: Color = Color::srgb(1.0, 0.8, 0.2);
+
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
+        app.add_event::<PlayerHit>();
+        app.init_resource::<Lives>();
         app.add_systems(OnEnter(GameStates::Game), setup_player);
-        app.add_systems(Update, move_player.run_if(in_state(GameStates::Game)));
+        app.add_systems(
+            Update,
+            (
+                (move_player, fire_player).run_if(human_controlled),
+                update_charge_bar,
+            )
+                .chain()
+                .run_if(in_state(GameStates::Game)),
+        );
     }
 }
 
 #[derive(Component)]
-struct Player;
+pub struct Player;
+
+/// Who flies the player's ship.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub enum PlayerController {
+    /// Keyboard and gamepad, see `move_player` and `fire_player`.
+    Human,
+    /// See `PilotPlugin`.
+    Ai(AiPilot),
+    /// The actions passed to `env::Environment::step`.
+    Agent,
+}
+
+/// Hits the player can still take, the game is lost when it reaches 0. Set
+/// when a game starts, there from the beginning so conditions on it can be
+/// checked in every state.
+#[derive(Resource, Default)]
+pub struct Lives(pub u32);
+
+/// Sent when the player got hit and lost a life.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct PlayerHit {
+    pub position: Vec2,
+}
 
 #[derive(Event)]
 pub struct PlayerShot {
     pub x: f32,
     pub y: f32,
+    /// Sent by autofire rather than by a button press.
+    pub auto: bool,
+    /// Charge of a charged shot, from 0 to 1.
+    pub charge: f32,
 }
 
-fn setup_player(mut cmd: Commands, assets: Res<load_assets::Assets>) {
+/// Charge built up by holding the fire button in the charge fire mode.
+#[derive(Component, Default)]
+struct ChargeMeter(f32);
+
+/// Shows the `ChargeMeter` above the player.
+#[derive(Component)]
+struct ChargeBar;
+
+fn setup_player(
+    mut cmd: Commands,
+    assets: Res<load_assets::Assets>,
+    config: Res<GameConfig>,
+    difficulty: Res<Difficulty>,
+    settings: Res<Settings>,
+    attract_mode: Option<Res<AttractMode>>,
+    mut animations: SpriteAnimations,
+) {
+    cmd.insert_resource(Lives(difficulty.settings().lives));
+    let controller = if attract_mode.is_some() {
+        PlayerController::Ai(AiPilot::new(
+            config.attract_pilot_reaction_time,
+            config.attract_pilot_aim_error,
+        ))
+    } else if settings.autopilot {
+        PlayerController::Ai(AiPilot::new(
+            config.autopilot_reaction_time,
+            config.autopilot_aim_error,
+        ))
+    } else {
+        PlayerController::Human
+    };
+    let thruster = animations.start("thruster");
     let player_sprite = assets.player.clone();
     cmd.spawn((
         Player,
+        controller,
+        Weapon::default(),
+        WeaponCooldown::default(),
+        settings.fire_mode,
+        ChargeMeter::default(),
         OnGameScreen,
-        Transform::from_xyz(0.0, -WORLD_HEIGHT / 2.0 + PADDING, 20.0),
+        Transform::from_xyz(0.0, -WORLD_HEIGHT / 2.0 + config.padding, 20.0),
+        // Leave out the narrow tip of the ship.
+        Collider::aabb(
+            Vec2::new(PLAYER_WIDTH, PLAYER_HEIGHT * 0.75),
+            CollisionLayers::new(layer::PLAYER, layer::ALIEN_BULLET | layer::POWER_UP),
+        )
+        .with_offset(Vec2::new(0.0, -PLAYER_HEIGHT * 0.125)),
         Sprite {
             image: player_sprite,
             custom_size: Some(Vec2::new(PLAYER_WIDTH, PLAYER_HEIGHT)),
             ..Default::default()
         },
-    ));
+    ))
+    .with_children(|parent| {
+        parent.spawn((
+            ChargeBar,
+            Sprite::from_color(CHARGE_BAR_COLOR, Vec2::new(0.0, CHARGE_BAR_HEIGHT)),
+            Transform::from_xyz(0.0, PLAYER_HEIGHT / 2.0 + CHARGE_BAR_HEIGHT, 0.0),
+            Visibility::Hidden,
+        ));
+        // Below the ship and behind it.
+        parent.spawn((
+            thruster,
+            Transform::from_xyz(0.0, -PLAYER_HEIGHT / 2.0, -0.1),
+        ));
+    });
+}
+
+fn human_controlled(controller: Query<&PlayerController>) -> bool {
+    matches!(controller.get_single(), Ok(PlayerController::Human))
 }
 
 fn move_player(
     gamepads: Query<(Entity, &Gamepad)>,
     player: Single<&mut Transform, With<Player>>,
     time: Res<Time>,
-    mut player_shot_event_writer: EventWriter<PlayerShot>,
     keyboard_input: Res<ButtonInput<KeyCode>>,
+    config: Res<GameConfig>,
 ) {
     let player = &mut *player.into_inner();
+    // Follows padding changes while the game is running.
+    player.translation.y = -WORLD_HEIGHT / 2.0 + config.padding;
     for (_entity, gamepad) in &gamepads {
         if let Some(left_stick_x) = gamepad.get(GamepadAxis::LeftStickX) {
             let reading: f32 = if left_stick_x.abs() >= 0.2 {
@@ -61,27 +175,80 @@ fn move_player(
                 0.0
             };
 
-            player.translation.x += reading * PLAYER_MAX_SPEED * time.delta().as_secs_f32();
-        }
-        if gamepad.just_pressed(GamepadButton::South) {
-            player_shot_event_writer.send(PlayerShot {
-                x: player.translation.x,
-                y: -WORLD_HEIGHT / 2.0 + PADDING + PLAYER_HEIGHT / 2.0,
-            });
+            player.translation.x += reading * config.player_max_speed * time.delta().as_secs_f32();
         }
     }
     if keyboard_input.pressed(KeyCode::ArrowLeft) {
-        player.translation.x -= PLAYER_MAX_SPEED * time.delta().as_secs_f32();
+        player.translation.x -= config.player_max_speed * time.delta().as_secs_f32();
     }
     if keyboard_input.pressed(KeyCode::ArrowRight) {
-        player.translation.x += PLAYER_MAX_SPEED * time.delta().as_secs_f32();
+        player.translation.x += config.player_max_speed * time.delta().as_secs_f32();
     }
     player.translation.x = player.translation.x.max(-WORLD_WIDTH / 2.0);
     player.translation.x = player.translation.x.min(WORLD_WIDTH / 2.0);
-    if keyboard_input.just_pressed(KeyCode::Space) {
-        player_shot_event_writer.send(PlayerShot {
-            x: player.translation.x,
-            y: -WORLD_HEIGHT / 2.0 + PADDING + PLAYER_HEIGHT / 2.0,
-        });
+}
+
+/// Turns the fire button into `PlayerShot`s, according to the fire mode.
+fn fire_player(
+    gamepads: Query<&Gamepad>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    player: Single<(&Transform, &FireMode, &mut ChargeMeter), With<Player>>,
+    time: Res<Time>,
+    config: Res<GameConfig>,
+    mut player_shot_event_writer: EventWriter<PlayerShot>,
+) {
+    let just_pressed = keyboard_input.just_pressed(KeyCode::Space)
+        || gamepads
+            .iter()
+            .any(|gamepad| gamepad.just_pressed(GamepadButton::South));
+    let pressed = keyboard_input.pressed(KeyCode::Space)
+        || gamepads
+            .iter()
+            .any(|gamepad| gamepad.pressed(GamepadButton::South));
+
+    let (transform, fire_mode, mut charge_meter) = player.into_inner();
+    let mut shot = PlayerShot {
+        x: transform.translation.x,
+        y: -WORLD_HEIGHT / 2.0 + config.padding + PLAYER_HEIGHT / 2.0,
+        auto: false,
+        charge: 0.0,
+    };
+    match fire_mode {
+        FireMode::Tap => {
+            if just_pressed {
+                player_shot_event_writer.send(shot);
+            }
+        }
+        FireMode::AutoFire => {
+            if pressed {
+                shot.auto = !just_pressed;
+                player_shot_event_writer.send(shot);
+            }
+        }
+        FireMode::Charge => {
+            if pressed {
+                charge_meter.0 = (charge_meter.0 + time.delta_secs() / FULL_CHARGE_TIME).min(1.0);
+            } else if charge_meter.0 > 0.0 {
+                shot.charge = charge_meter.0;
+                charge_meter.0 = 0.0;
+                player_shot_event_writer.send(shot);
+            }
+        }
     }
 }
+
+fn update_charge_bar(
+    charge_meter: Query<&ChargeMeter, Changed<ChargeMeter>>,
+    charge_bar: Single<(&mut Sprite, &mut Visibility), With<ChargeBar>>,
+) {
+    let Ok(charge_meter) = charge_meter.get_single() else {
+        return;
+    };
+    let (mut sprite, mut visibility) = charge_bar.into_inner();
+    sprite.custom_size = Some(Vec2::new(charge_meter.0 * PLAYER_WIDTH, CHARGE_BAR_HEIGHT));
+    *visibility = if charge_meter.0 > 0.0 {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+}
@@ -0,0 +1,234 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::alien::{AlienKilled, FormationClock};
+use crate::audio::{PlaySfxExt, SoundId};
+use crate::collision::{layer, Collider, Collision, CollisionLayers, DetectCollisions};
+use crate::game::{GameRng, OnGameScreen};
+use crate::load_assets;
+use crate::player::{Lives, Player};
+use crate::weapon::{Weapon, Weapons, DEFAULT_WEAPON};
+use crate::{GameStates, WORLD_HEIGHT};
+
+/// Chance of a shot down alien to drop a power-up.
+const DROP_CHANCE: f64 = 0.1;
+const POWER_UP_SIZE: f32 = 6f32;
+const POWER_UP_FALL_SPEED: f32 = 32f32;
+const SHIELD_COLOR: Color = Color::srgb(0.5, 0.8, 1.0);
+/// Weapon in `assets/*.weapons.ron` the spread shot power-up switches to.
+const SPREAD_SHOT_WEAPON: &str = "spread";
+
+pub struct PowerUpPlugin;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PowerUpKind {
+    RapidFire,
+    SpreadShot,
+    Shield,
+    ExtraLife,
+    SlowTime,
+}
+
+impl PowerUpKind {
+    const ALL: [PowerUpKind; 5] = [
+        PowerUpKind::RapidFire,
+        PowerUpKind::SpreadShot,
+        PowerUpKind::Shield,
+        PowerUpKind::ExtraLife,
+        PowerUpKind::SlowTime,
+    ];
+
+    /// How long the effect lasts, `None` for effects applied once.
+    fn duration(self) -> Option<Duration> {
+        match self {
+            PowerUpKind::RapidFire => Some(Duration::from_secs(8)),
+            PowerUpKind::SpreadShot => Some(Duration::from_secs(8)),
+            PowerUpKind::Shield => Some(Duration::from_secs(6)),
+            PowerUpKind::ExtraLife => None,
+            PowerUpKind::SlowTime => Some(Duration::from_secs(5)),
+        }
+    }
+
+    fn color(self) -> Color {
+        match self {
+            PowerUpKind::RapidFire => Color::srgb(1.0, 0.8, 0.2),
+            PowerUpKind::SpreadShot => Color::srgb(1.0, 0.4, 0.8),
+            PowerUpKind::Shield => SHIELD_COLOR,
+            PowerUpKind::ExtraLife => Color::srgb(0.4, 1.0, 0.4),
+            PowerUpKind::SlowTime => Color::srgb(0.7, 0.5, 1.0),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PowerUpKind::RapidFire => "Schnellfeuer",
+            PowerUpKind::SpreadShot => "Streuschuss",
+            PowerUpKind::Shield => "Schild",
+            PowerUpKind::ExtraLife => "Extraleben",
+            PowerUpKind::SlowTime => "Zeitlupe",
+        }
+    }
+}
+
+/// A power-up falling down, waiting to be collected.
+#[derive(Component)]
+struct PowerUp(PowerUpKind);
+
+/// Timed effects currently running, with the time they have left.
+#[derive(Resource, Default)]
+pub struct ActivePowerUps(BTreeMap<PowerUpKind, Timer>);
+
+impl ActivePowerUps {
+    pub fn is_active(&self, kind: PowerUpKind) -> bool {
+        self.0.contains_key(&kind)
+    }
+
+    /// The running effects with the seconds they have left.
+    pub fn remaining(&self) -> impl Iterator<Item = (PowerUpKind, f32)> + '_ {
+        self.0
+            .iter()
+            .map(|(kind, timer)| (*kind, timer.remaining_secs()))
+    }
+}
+
+impl Plugin for PowerUpPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ActivePowerUps>();
+        app.add_systems(OnEnter(GameStates::Game), setup_power_ups);
+        app.add_systems(
+            Update,
+            (
+                drop_power_ups,
+                move_power_ups,
+                collect_power_ups.after(DetectCollisions),
+                tick_power_ups,
+                apply_power_ups
+                    .run_if(resource_changed::<ActivePowerUps>.or(on_event::<AssetEvent<Weapons>>)),
+            )
+                .chain()
+                .run_if(in_state(GameStates::Game)),
+        );
+    }
+}
+
+fn setup_power_ups(mut active: ResMut<ActivePowerUps>) {
+    active.0.clear();
+}
+
+fn drop_power_ups(
+    mut alien_killed: EventReader<AlienKilled>,
+    mut rng: ResMut<GameRng>,
+    mut cmd: Commands,
+) {
+    let rng = &mut rng.0;
+    for killed in alien_killed.read() {
+        if !rng.gen_bool(DROP_CHANCE) {
+            continue;
+        }
+        let kind = PowerUpKind::ALL[rng.gen_range(0..PowerUpKind::ALL.len())];
+        cmd.spawn((
+            PowerUp(kind),
+            OnGameScreen,
+            Sprite::from_color(kind.color(), Vec2::splat(POWER_UP_SIZE)),
+            Transform::from_translation(killed.position.extend(15.0)),
+            Collider::circle(
+                POWER_UP_SIZE / 2.0,
+                CollisionLayers::new(layer::POWER_UP, layer::PLAYER),
+            ),
+        ));
+    }
+}
+
+fn move_power_ups(
+    mut power_ups: Query<(Entity, &mut Transform), With<PowerUp>>,
+    time: Res<Time>,
+    mut cmd: Commands,
+) {
+    for (entity, mut transform) in &mut power_ups {
+        transform.translation.y -= POWER_UP_FALL_SPEED * time.delta_secs();
+        if transform.translation.y <= -WORLD_HEIGHT / 2.0 {
+            cmd.entity(entity).despawn();
+        }
+    }
+}
+
+fn collect_power_ups(
+    mut collisions: EventReader<Collision>,
+    power_ups: Query<&PowerUp>,
+    mut active: ResMut<ActivePowerUps>,
+    mut lives: ResMut<Lives>,
+    mut cmd: Commands,
+) {
+    for (power_up, _player) in collisions
+        .read()
+        .filter_map(|collision| collision.pair(layer::POWER_UP, layer::PLAYER))
+    {
+        let Ok(PowerUp(kind)) = power_ups.get(power_up) else {
+            continue;
+        };
+        cmd.entity(power_up).despawn();
+        cmd.play_sfx(SoundId::PowerUp);
+        match kind.duration() {
+            // Collecting a running effect again restarts it.
+            Some(duration) => {
+                active
+                    .0
+                    .insert(*kind, Timer::new(duration, TimerMode::Once));
+            }
+            None => lives.0 += 1,
+        }
+    }
+}
+
+fn tick_power_ups(time: Res<Time>, mut active: ResMut<ActivePowerUps>) {
+    if active.0.is_empty() {
+        return;
+    }
+    // Ticking would mark the resource as changed every frame, the weapon
+    // only has to be updated when an effect actually ran out.
+    let before = active.0.len();
+    let timers = &mut active.bypass_change_detection().0;
+    for timer in timers.values_mut() {
+        timer.tick(time.delta());
+    }
+    timers.retain(|_, timer| !timer.finished());
+    if timers.len() != before {
+        active.set_changed();
+    }
+}
+
+fn apply_power_ups(
+    active: Res<ActivePowerUps>,
+    assets: Res<load_assets::Assets>,
+    weapons: Res<bevy::prelude::Assets<Weapons>>,
+    player: Single<(&mut Weapon, &mut Sprite), With<Player>>,
+    mut clock: ResMut<FormationClock>,
+) {
+    let (mut weapon, mut sprite) = player.into_inner();
+    let name = if active.is_active(PowerUpKind::SpreadShot) {
+        SPREAD_SHOT_WEAPON
+    } else {
+        DEFAULT_WEAPON
+    };
+    let mut new_weapon = weapons
+        .get(&assets.weapons)
+        .map(|weapons| weapons.get(name))
+        .unwrap_or_default();
+    if active.is_active(PowerUpKind::RapidFire) {
+        new_weapon = new_weapon.with_fire_rate(2.5);
+    }
+    *weapon = new_weapon;
+    sprite.color = if active.is_active(PowerUpKind::Shield) {
+        SHIELD_COLOR
+    } else {
+        Color::WHITE
+    };
+    clock.speed = if active.is_active(PowerUpKind::SlowTime) {
+        0.5
+    } else {
+        1.0
+    };
+}
@@ -0,0 +1,82 @@
+use std::fmt;
+use std::marker::PhantomData;
+
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, LoadContext};
+use bevy::prelude::*;
+use serde::de::DeserializeOwned;
+
+/// Data files which are written by hand in RON and loaded through the
+/// asset server, so they take part in hot reloading.
+pub trait RonAsset: Asset + DeserializeOwned {
+    /// File extensions (without the leading dot) this asset is loaded from.
+    const EXTENSIONS: &'static [&'static str];
+
+    /// Checks the deserialized data for values the game can't work with.
+    fn validate(&self) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+pub struct RonAssetLoader<A> {
+    _marker: PhantomData<fn() -> A>,
+}
+
+impl<A> Default for RonAssetLoader<A> {
+    fn default() -> Self {
+        Self {
+            _marker: PhantomData,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum RonAssetError {
+    Io(std::io::Error),
+    Parse(ron::error::SpannedError),
+    Invalid(String),
+}
+
+impl fmt::Display for RonAssetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RonAssetError::Io(err) => write!(f, "could not read file: {err}"),
+            RonAssetError::Parse(err) => write!(f, "could not parse RON: {err}"),
+            RonAssetError::Invalid(reason) => write!(f, "invalid values: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for RonAssetError {}
+
+impl<A: RonAsset> AssetLoader for RonAssetLoader<A> {
+    type Asset = A;
+    type Settings = ();
+    type Error = RonAssetError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<A, RonAssetError> {
+        let mut bytes = Vec::new();
+        reader
+            .read_to_end(&mut bytes)
+            .await
+            .map_err(RonAssetError::Io)?;
+        let asset: A = ron::de::from_bytes(&bytes).map_err(RonAssetError::Parse)?;
+        asset.validate().map_err(RonAssetError::Invalid)?;
+        Ok(asset)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        A::EXTENSIONS
+    }
+}
+
+/// Registers `A` as an asset together with its RON loader.
+pub fn register_ron_asset<A: RonAsset>(app: &mut App) {
+    app.init_asset::<A>()
+        .init_asset_loader::<RonAssetLoader<A>>();
+}
@@ -0,0 +1,89 @@
+use bevy::prelude::*;
+use bevy::render::camera::{ScalingMode, Viewport};
+use bevy::window::PrimaryWindow;
+
+use crate::config::GameConfig;
+use crate::game::MainCamera;
+use crate::settings::Settings;
+use crate::{DISPLAY_HEIGHT, WORLD_HEIGHT, WORLD_WIDTH};
+
+/// Physical pixels per world unit the UI sizes are made for.
+pub const UI_DESIGN_SCALE: f32 = DISPLAY_HEIGHT / WORLD_HEIGHT;
+
+/// Keeps the whole playfield visible and undistorted on any window size,
+/// with letterbox bars where the window's aspect ratio differs.
+pub struct ScalingPlugin;
+
+impl Plugin for ScalingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(PostUpdate, fit_playfield_to_window);
+    }
+}
+
+/// Size of the visible area, which is the world plus the padding around it.
+pub fn playfield_size(config: &GameConfig) -> Vec2 {
+    Vec2::new(WORLD_WIDTH, WORLD_HEIGHT) + 2.0 * config.padding
+}
+
+/// Physical pixels per world unit the playfield is shown at in a window of
+/// `window_size` physical pixels.
+fn playfield_scale(window_size: Vec2, playfield_size: Vec2, pixel_perfect: bool) -> f32 {
+    let scale = (window_size / playfield_size).min_element();
+    // A window too small for a single pixel per world unit gets the fitting
+    // scale anyway, rather than a cropped playfield.
+    if pixel_perfect && scale >= 1.0 {
+        scale.floor()
+    } else {
+        scale
+    }
+}
+
+fn fit_playfield_to_window(
+    window: Single<&Window, With<PrimaryWindow>>,
+    config: Res<GameConfig>,
+    settings: Res<Settings>,
+    camera: Single<(&mut Camera, &mut OrthographicProjection), With<MainCamera>>,
+    mut ui_scale: ResMut<UiScale>,
+) {
+    let window_size = window.physical_size();
+    if window_size.min_element() == 0 {
+        // Minimized.
+        return;
+    }
+    let playfield_size = playfield_size(&config);
+    let scale = playfield_scale(
+        window_size.as_vec2(),
+        playfield_size,
+        settings.pixel_perfect,
+    );
+    let viewport_size = (playfield_size * scale)
+        .as_uvec2()
+        .clamp(UVec2::ONE, window_size);
+    let viewport_position = (window_size - viewport_size) / 2;
+
+    let (mut camera, mut projection) = camera.into_inner();
+    let viewport_changed = camera.viewport.as_ref().is_none_or(|viewport| {
+        viewport.physical_position != viewport_position || viewport.physical_size != viewport_size
+    });
+    if viewport_changed {
+        camera.viewport = Some(Viewport {
+            physical_position: viewport_position,
+            physical_size: viewport_size,
+            ..default()
+        });
+    }
+    let scaling_mode = ScalingMode::Fixed {
+        width: playfield_size.x,
+        height: playfield_size.y,
+    };
+    if !matches!(projection.scaling_mode, ScalingMode::Fixed { width, height } if width == playfield_size.x && height == playfield_size.y)
+    {
+        projection.scaling_mode = scaling_mode;
+    }
+    // UI is laid out in the main camera's viewport, its sizes follow the
+    // playfield's scale.
+    let new_ui_scale = scale / (UI_DESIGN_SCALE * window.scale_factor());
+    if ui_scale.0 != new_ui_scale {
+        ui_scale.0 = new_ui_scale;
+    }
+}
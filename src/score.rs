@@ -0,0 +1,68 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::difficulty::Difficulty;
+use crate::settings::Settings;
+use crate::GameStates;
+
+const HIGH_SCORES_PER_DIFFICULTY: usize = 10;
+
+pub struct ScorePlugin;
+
+/// Score of the running (or just finished) game.
+#[derive(Resource, Debug, Clone, Copy, Default)]
+pub struct Score {
+    pub points: u32,
+    pub difficulty: Difficulty,
+    /// Played by the autopilot, which doesn't make it into the high scores.
+    pub autopilot: bool,
+}
+
+/// Best scores, kept apart per difficulty so they stay comparable. Like the
+/// `Settings`, they only last until the game is closed.
+#[derive(Resource, Debug, Default)]
+pub struct HighScores(HashMap<Difficulty, Vec<u32>>);
+
+impl HighScores {
+    /// The best scores of `difficulty`, the highest first.
+    pub fn scores(&self, difficulty: Difficulty) -> &[u32] {
+        self.0.get(&difficulty).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn best(&self, difficulty: Difficulty) -> Option<u32> {
+        self.0
+            .get(&difficulty)
+            .and_then(|scores| scores.first().copied())
+    }
+
+    fn record(&mut self, score: &Score) {
+        let scores = self.0.entry(score.difficulty).or_default();
+        let index = scores.partition_point(|points| *points >= score.points);
+        scores.insert(index, score.points);
+        scores.truncate(HIGH_SCORES_PER_DIFFICULTY);
+    }
+}
+
+impl Plugin for ScorePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Score>();
+        app.init_resource::<HighScores>();
+        app.add_systems(OnEnter(GameStates::Game), reset_score);
+        app.add_systems(OnEnter(GameStates::End), record_score);
+    }
+}
+
+fn reset_score(mut score: ResMut<Score>, difficulty: Res<Difficulty>, settings: Res<Settings>) {
+    *score = Score {
+        points: 0,
+        difficulty: *difficulty,
+        autopilot: settings.autopilot,
+    };
+}
+
+fn record_score(score: Res<Score>, mut high_scores: ResMut<HighScores>) {
+    if !score.autopilot {
+        high_scores.record(&score);
+    }
+}
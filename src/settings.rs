@@ -0,0 +1,107 @@
+use bevy::prelude::*;
+
+use crate::audio::Bus;
+
+/// Player preferences, changed in the settings menu.
+#[derive(Resource, Debug, Clone)]
+pub struct Settings {
+    /// Given to the player's ship when a game starts.
+    pub fire_mode: FireMode,
+    /// The camera effects can be turned off for players sensitive to motion
+    /// or flashing lights.
+    pub screen_shake: bool,
+    pub hit_stop: bool,
+    pub flash: bool,
+    /// Scales the playfield by whole multiples only, so every world unit
+    /// covers the same number of pixels. Leaves wider letterbox bars.
+    pub pixel_perfect: bool,
+    /// Scanlines, a curved screen and the overlay strips of the arcade
+    /// cabinet, see `CrtPlugin`.
+    pub crt: bool,
+    /// Music in the menus. The march during the game always plays.
+    pub menu_music: bool,
+    /// Synthesizes the sound effects from `assets/sounds.sfxr.ron` instead
+    /// of playing the recordings.
+    pub synth_sounds: bool,
+    /// Pans the sounds of the game to where they happen on the playfield.
+    pub stereo: bool,
+    /// The AI pilot plays instead of the player, for soak and balance
+    /// testing. Its scores are not recorded.
+    pub autopilot: bool,
+    /// Volumes of the mixer buses, from 0 to 1.
+    pub sfx_volume: f32,
+    pub music_volume: f32,
+    pub ui_volume: f32,
+}
+
+impl Settings {
+    pub fn bus_volume(&self, bus: Bus) -> f32 {
+        match bus {
+            Bus::Sfx => self.sfx_volume,
+            Bus::Music => self.music_volume,
+            Bus::Ui => self.ui_volume,
+        }
+    }
+
+    pub fn bus_volume_mut(&mut self, bus: Bus) -> &mut f32 {
+        match bus {
+            Bus::Sfx => &mut self.sfx_volume,
+            Bus::Music => &mut self.music_volume,
+            Bus::Ui => &mut self.ui_volume,
+        }
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            fire_mode: FireMode::default(),
+            screen_shake: true,
+            hit_stop: true,
+            flash: true,
+            pixel_perfect: false,
+            crt: false,
+            menu_music: true,
+            synth_sounds: false,
+            stereo: true,
+            autopilot: false,
+            sfx_volume: 1.0,
+            music_volume: 1.0,
+            ui_volume: 1.0,
+        }
+    }
+}
+
+/// What holding the fire button does, for the ship it is a component of.
+#[derive(Component, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FireMode {
+    /// Every press fires a single shot.
+    #[default]
+    Tap,
+    /// Keeps firing as fast as the weapon allows while the button is held.
+    AutoFire,
+    /// Holding the button charges a bigger, piercing shot fired on release.
+    Charge,
+}
+
+impl FireMode {
+    const ALL: [FireMode; 3] = [FireMode::Tap, FireMode::AutoFire, FireMode::Charge];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            FireMode::Tap => "Einzelschuss",
+            FireMode::AutoFire => "Dauerfeuer",
+            FireMode::Charge => "Aufladen",
+        }
+    }
+
+    pub fn next(self) -> FireMode {
+        let index = FireMode::ALL.iter().position(|mode| *mode == self).unwrap();
+        FireMode::ALL[(index + 1) % FireMode::ALL.len()]
+    }
+
+    pub fn previous(self) -> FireMode {
+        let index = FireMode::ALL.iter().position(|mode| *mode == self).unwrap();
+        FireMode::ALL[(index + FireMode::ALL.len() - 1) % FireMode::ALL.len()]
+    }
+}
@@ -0,0 +1,292 @@
+use std::collections::BTreeMap;
+use std::f32::consts::TAU;
+use std::time::Duration;
+
+use bevy::audio::{AddAudioSource, Source};
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::Deserialize;
+
+use crate::ron_asset::{register_ron_asset, RonAsset};
+
+const SAMPLE_RATE: u32 = 44_100;
+/// Longest sound a preset may describe, in seconds.
+const MAX_LENGTH: f32 = 5.0;
+/// Random values per period of the noise wave, fewer sound lower.
+const NOISE_STEPS: f32 = 32.0;
+
+/// Sound effects synthesized from a few parameters, in the manner of sfxr.
+/// The presets are loaded from `assets/*.sfxr.ron`.
+pub struct SfxrPlugin;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+pub enum SfxrWave {
+    #[default]
+    Square,
+    Sawtooth,
+    Sine,
+    /// White noise, for explosions and hits. Its frequency decides how rough
+    /// it sounds.
+    Noise,
+}
+
+/// Describes one sound effect.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SfxrParams {
+    #[serde(default)]
+    pub wave: SfxrWave,
+    /// Starting frequency in Hz.
+    pub frequency: f32,
+    /// Octaves per second the frequency rises by, negative values let it fall.
+    #[serde(default)]
+    pub frequency_slide: f32,
+    /// The sound stops once a falling frequency reaches this, in Hz.
+    #[serde(default = "default_min_frequency")]
+    pub min_frequency: f32,
+    /// Fraction of the period the square wave is high, from 0 to 1.
+    #[serde(default = "half")]
+    pub duty: f32,
+    /// Change of the duty per second.
+    #[serde(default)]
+    pub duty_slide: f32,
+    /// Fraction of the frequency the vibrato wobbles by.
+    #[serde(default)]
+    pub vibrato_depth: f32,
+    /// In Hz.
+    #[serde(default)]
+    pub vibrato_speed: f32,
+    /// Seconds the sound takes to get loud.
+    #[serde(default)]
+    pub attack: f32,
+    /// Seconds the sound stays loud.
+    pub sustain: f32,
+    /// Seconds the sound takes to fade out.
+    pub decay: f32,
+    /// Extra volume at the start of the sustain, fading over it.
+    #[serde(default)]
+    pub punch: f32,
+    /// From 0 to 1.
+    #[serde(default = "half")]
+    pub volume: f32,
+    /// How much the frequency and length change randomly every time the
+    /// sound is played, from 0 to 1.
+    #[serde(default)]
+    pub variation: f32,
+}
+
+fn default_min_frequency() -> f32 {
+    20.0
+}
+
+fn half() -> f32 {
+    0.5
+}
+
+impl SfxrParams {
+    fn length(&self) -> f32 {
+        self.attack + self.sustain + self.decay
+    }
+
+    /// A random variation of the sound, see `variation`.
+    pub fn vary(&self, rng: &mut impl Rng) -> SfxrParams {
+        let mut jitter = || 1.0 + self.variation * rng.gen_range(-0.5..=0.5);
+        SfxrParams {
+            frequency: self.frequency * jitter(),
+            sustain: self.sustain * jitter(),
+            decay: self.decay * jitter(),
+            ..self.clone()
+        }
+    }
+
+    /// Volume of the envelope at `t` seconds.
+    fn envelope(&self, t: f32) -> f32 {
+        if t < self.attack {
+            t / self.attack
+        } else if t < self.attack + self.sustain {
+            let sustained = (t - self.attack) / self.sustain;
+            1.0 + self.punch * (1.0 - sustained)
+        } else {
+            1.0 - (t - self.attack - self.sustain) / self.decay
+        }
+    }
+}
+
+/// The presets sound effects are synthesized from, by name.
+#[derive(Asset, TypePath, Debug, Clone, Deserialize)]
+pub struct SfxrPresets {
+    pub presets: BTreeMap<String, SfxrParams>,
+}
+
+impl RonAsset for SfxrPresets {
+    const EXTENSIONS: &'static [&'static str] = &["sfxr.ron"];
+
+    fn validate(&self) -> Result<(), String> {
+        for (name, params) in &self.presets {
+            for (field, value) in [
+                ("frequency", params.frequency),
+                ("frequency_slide", params.frequency_slide),
+                ("min_frequency", params.min_frequency),
+                ("duty", params.duty),
+                ("duty_slide", params.duty_slide),
+                ("vibrato_depth", params.vibrato_depth),
+                ("vibrato_speed", params.vibrato_speed),
+                ("attack", params.attack),
+                ("sustain", params.sustain),
+                ("decay", params.decay),
+                ("punch", params.punch),
+                ("volume", params.volume),
+                ("variation", params.variation),
+            ] {
+                if !value.is_finite() {
+                    return Err(format!("{name}: {field} has to be a number"));
+                }
+            }
+            if params.frequency <= 0.0 || params.min_frequency < 0.0 {
+                return Err(format!("{name}: frequencies have to be positive"));
+            }
+            if params.attack < 0.0 || params.sustain < 0.0 || params.decay < 0.0 {
+                return Err(format!("{name}: durations can't be negative"));
+            }
+            if !(params.length() > 0.0 && params.length() <= MAX_LENGTH) {
+                return Err(format!(
+                    "{name}: attack, sustain and decay have to add up to more than 0 and at most {MAX_LENGTH} seconds"
+                ));
+            }
+            for (field, value) in [
+                ("duty", params.duty),
+                ("volume", params.volume),
+                ("variation", params.variation),
+            ] {
+                if !(0.0..=1.0).contains(&value) {
+                    return Err(format!("{name}: {field} has to be between 0 and 1"));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A sound synthesized while it plays, created from a preset every time it
+/// is played.
+#[derive(Asset, TypePath, Debug, Clone)]
+pub struct SfxrSound {
+    pub params: SfxrParams,
+    /// Seed of the noise wave.
+    pub seed: u64,
+}
+
+impl Decodable for SfxrSound {
+    type DecoderItem = f32;
+    type Decoder = SfxrDecoder;
+
+    fn decoder(&self) -> SfxrDecoder {
+        SfxrDecoder {
+            params: self.params.clone(),
+            rng: StdRng::seed_from_u64(self.seed),
+            sample: 0,
+            phase: 0.0,
+            noise: 0.0,
+            noise_step: 0,
+        }
+    }
+}
+
+pub struct SfxrDecoder {
+    params: SfxrParams,
+    rng: StdRng,
+    sample: u32,
+    /// Position in the current period, from 0 to 1.
+    phase: f32,
+    noise: f32,
+    /// Part of the period the current noise value belongs to.
+    noise_step: u32,
+}
+
+impl Iterator for SfxrDecoder {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let params = &self.params;
+        let t = self.sample as f32 / SAMPLE_RATE as f32;
+        if t >= params.length() {
+            return None;
+        }
+        let vibrato = 1.0 + params.vibrato_depth * (TAU * params.vibrato_speed * t).sin();
+        let frequency = params.frequency * (params.frequency_slide * t).exp2() * vibrato;
+        if frequency < params.min_frequency {
+            return None;
+        }
+        self.sample += 1;
+        self.phase = (self.phase + frequency / SAMPLE_RATE as f32).fract();
+
+        let wave = match params.wave {
+            SfxrWave::Square => {
+                let duty = (params.duty + params.duty_slide * t).clamp(0.05, 0.95);
+                if self.phase < duty {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+            SfxrWave::Sawtooth => 1.0 - 2.0 * self.phase,
+            SfxrWave::Sine => (TAU * self.phase).sin(),
+            SfxrWave::Noise => {
+                let step = (self.phase * NOISE_STEPS) as u32;
+                if step != self.noise_step {
+                    self.noise_step = step;
+                    self.noise = self.rng.gen_range(-1.0..=1.0);
+                }
+                self.noise
+            }
+        };
+        Some((params.volume * params.envelope(t) * wave).clamp(-1.0, 1.0))
+    }
+}
+
+impl Source for SfxrDecoder {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        Some(Duration::from_secs_f32(self.params.length()))
+    }
+}
+
+impl Plugin for SfxrPlugin {
+    fn build(&self, app: &mut App) {
+        register_ron_asset::<SfxrPresets>(app);
+        // The headless `env::Environment` has no audio output, it creates
+        // the sounds but never plays them.
+        if app.is_plugin_added::<bevy::audio::AudioPlugin>() {
+            app.add_audio_source::<SfxrSound>();
+        } else {
+            app.init_asset::<SfxrSound>();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_which_are_not_a_number_are_rejected() {
+        let mut presets: SfxrPresets =
+            ron::from_str(include_str!("../assets/sounds.sfxr.ron")).unwrap();
+        assert_eq!(presets.validate(), Ok(()));
+        for params in presets.presets.values_mut() {
+            params.volume = f32::NAN;
+        }
+        assert!(presets.validate().is_err());
+    }
+}
@@ -0,0 +1,196 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::collision::{layer, Body, Collider, CollisionLayers};
+use crate::{WORLD_HEIGHT, WORLD_WIDTH};
+
+/// Broad phase for the collision detection: a uniform grid over the world,
+/// rebuilt from scratch every tick. Only bodies sharing a cell are tested
+/// against each other.
+///
+/// Bodies outside of the world are put into the cells at its border, so
+/// they are still found, just not as fast.
+#[derive(Resource)]
+pub struct SpatialGrid {
+    cell_size: f32,
+    columns: usize,
+    rows: usize,
+    cells: Vec<Vec<usize>>,
+    /// Cell range (`min_column`, `min_row`, `max_column`, `max_row`) of
+    /// every body, indexed like the bodies.
+    ranges: Vec<[usize; 4]>,
+}
+
+impl SpatialGrid {
+    pub fn new(cell_size: f32) -> Self {
+        let columns = (WORLD_WIDTH / cell_size).ceil().max(1.0) as usize;
+        let rows = (WORLD_HEIGHT / cell_size).ceil().max(1.0) as usize;
+        SpatialGrid {
+            cell_size,
+            columns,
+            rows,
+            cells: vec![Vec::new(); columns * rows],
+            ranges: Vec::new(),
+        }
+    }
+
+    fn column(&self, x: f32) -> usize {
+        (((x + WORLD_WIDTH / 2.0) / self.cell_size).floor().max(0.0) as usize).min(self.columns - 1)
+    }
+
+    fn row(&self, y: f32) -> usize {
+        (((y + WORLD_HEIGHT / 2.0) / self.cell_size).floor().max(0.0) as usize).min(self.rows - 1)
+    }
+
+    fn rebuild(&mut self, bodies: &[Body]) {
+        for cell in &mut self.cells {
+            cell.clear();
+        }
+        self.ranges.clear();
+        for (index, body) in bodies.iter().enumerate() {
+            let bounds = body.collider.bounds(body.position);
+            let range = [
+                self.column(bounds.min.x),
+                self.row(bounds.min.y),
+                self.column(bounds.max.x),
+                self.row(bounds.max.y),
+            ];
+            self.ranges.push(range);
+            for row in range[1]..=range[3] {
+                for column in range[0]..=range[2] {
+                    self.cells[row * self.columns + column].push(index);
+                }
+            }
+        }
+    }
+
+    /// Fills `pairs` with the indices of colliding bodies, sorted and with
+    /// the lower index first, just like `find_collisions_brute_force`.
+    pub fn find_collisions(&mut self, bodies: &[Body], pairs: &mut Vec<(usize, usize)>) {
+        self.rebuild(bodies);
+        pairs.clear();
+        for (cell_index, cell) in self.cells.iter().enumerate() {
+            let column = cell_index % self.columns;
+            let row = cell_index / self.columns;
+            for (i, &a) in cell.iter().enumerate() {
+                for &b in &cell[i + 1..] {
+                    // Bodies spanning several cells meet in all of them,
+                    // only test them in the first one they share.
+                    let (range_a, range_b) = (self.ranges[a], self.ranges[b]);
+                    if range_a[0].max(range_b[0]) != column || range_a[1].max(range_b[1]) != row {
+                        continue;
+                    }
+                    if bodies[a].collides_with(&bodies[b]) {
+                        pairs.push((a.min(b), a.max(b)));
+                    }
+                }
+            }
+        }
+        pairs.sort_unstable();
+    }
+}
+
+/// A random box or circle on the layers of the aliens, the player or their
+/// bullets. Shared by the tests and `benches/collision.rs`.
+#[doc(hidden)]
+pub fn random_collider(rng: &mut impl Rng) -> Collider {
+    let layers = [
+        CollisionLayers::new(layer::ALIEN, layer::PLAYER_BULLET),
+        CollisionLayers::new(layer::PLAYER_BULLET, layer::ALIEN),
+        CollisionLayers::new(layer::ALIEN_BULLET, layer::PLAYER),
+        CollisionLayers::new(layer::PLAYER, layer::ALIEN_BULLET),
+    ];
+    let layers = layers[rng.gen_range(0..layers.len())];
+    if rng.gen_bool(0.5) {
+        Collider::aabb(
+            Vec2::new(rng.gen_range(1.0..10.0), rng.gen_range(1.0..10.0)),
+            layers,
+        )
+    } else {
+        Collider::circle(rng.gen_range(0.5..5.0), layers)
+    }
+}
+
+/// `count` random bodies all over the world. Some stick out of it, like
+/// bullets leaving it.
+#[doc(hidden)]
+pub fn random_bodies(count: usize, rng: &mut impl Rng) -> Vec<Body> {
+    (0..count)
+        .map(|_| Body {
+            collider: random_collider(rng),
+            position: Vec2::new(
+                rng.gen_range(-0.55..0.55) * WORLD_WIDTH,
+                rng.gen_range(-0.55..0.55) * WORLD_HEIGHT,
+            ),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::collision::find_collisions_brute_force;
+
+    const CELL_SIZE: f32 = 16.0;
+
+    fn assert_same_as_brute_force(bodies: &[Body]) {
+        let mut grid_pairs = Vec::new();
+        let mut brute_force_pairs = Vec::new();
+        SpatialGrid::new(CELL_SIZE).find_collisions(bodies, &mut grid_pairs);
+        find_collisions_brute_force(bodies, &mut brute_force_pairs);
+        assert_eq!(grid_pairs, brute_force_pairs);
+    }
+
+    #[test]
+    fn finds_the_same_collisions_as_brute_force() {
+        let mut rng = StdRng::seed_from_u64(0x5ace);
+        for count in [0, 1, 10, 100, 1_000] {
+            let bodies = random_bodies(count, &mut rng);
+            assert_same_as_brute_force(&bodies);
+        }
+    }
+
+    #[test]
+    fn finds_collisions_across_cell_borders() {
+        let mut rng = StdRng::seed_from_u64(0xb0de);
+        // Every body sits close to a corner of a cell, so it covers up to
+        // four cells and meets its neighbours in the cells of either side.
+        let bodies: Vec<Body> = (0..500)
+            .map(|_| {
+                let corner = Vec2::new(
+                    rng.gen_range(0..=(WORLD_WIDTH / CELL_SIZE) as i32) as f32,
+                    rng.gen_range(0..=(WORLD_HEIGHT / CELL_SIZE) as i32) as f32,
+                ) * CELL_SIZE
+                    - Vec2::new(WORLD_WIDTH, WORLD_HEIGHT) / 2.0;
+                let offset = Vec2::new(rng.gen_range(-3.0..3.0), rng.gen_range(-3.0..3.0));
+                Body {
+                    collider: random_collider(&mut rng),
+                    position: corner + offset,
+                }
+            })
+            .collect();
+        assert_same_as_brute_force(&bodies);
+    }
+
+    #[test]
+    fn reports_a_pair_sharing_several_cells_once() {
+        let layers = CollisionLayers::new(layer::ALIEN, layer::PLAYER_BULLET);
+        let bullet_layers = CollisionLayers::new(layer::PLAYER_BULLET, layer::ALIEN);
+        let bodies = [
+            Body {
+                collider: Collider::aabb(Vec2::splat(CELL_SIZE * 2.0), layers),
+                position: Vec2::ZERO,
+            },
+            Body {
+                collider: Collider::circle(CELL_SIZE, bullet_layers),
+                position: Vec2::new(1.0, 1.0),
+            },
+        ];
+        let mut pairs = Vec::new();
+        SpatialGrid::new(CELL_SIZE).find_collisions(&bodies, &mut pairs);
+        assert_eq!(pairs, [(0, 1)]);
+    }
+}
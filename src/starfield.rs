@@ -0,0 +1,114 @@
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::config::GameConfig;
+use crate::scaling::playfield_size;
+use crate::GameStates;
+
+/// The same seed every time, so the sky doesn't change between runs.
+const STARFIELD_SEED: u64 = 0x5741_5253;
+/// World units per second the nearest stars scroll by while playing.
+const SCROLL_SPEED: f32 = 24.0;
+/// Fraction of the scroll speed the stars drift by outside of the game.
+const DRIFT_FACTOR: f32 = 0.15;
+
+pub struct StarfieldPlugin;
+
+/// A layer of stars, the farther ones are smaller, darker and slower.
+struct StarLayer {
+    /// Fraction of the scroll speed.
+    speed: f32,
+    size: f32,
+    brightness: f32,
+    /// Behind everything else, which starts at z=19.
+    z: f32,
+}
+
+const LAYERS: [StarLayer; 3] = [
+    StarLayer {
+        speed: 0.2,
+        size: 0.5,
+        brightness: 0.35,
+        z: 1.0,
+    },
+    StarLayer {
+        speed: 0.5,
+        size: 0.5,
+        brightness: 0.6,
+        z: 2.0,
+    },
+    StarLayer {
+        speed: 1.0,
+        size: 1.0,
+        brightness: 0.9,
+        z: 3.0,
+    },
+];
+
+#[derive(Component)]
+struct Star {
+    speed: f32,
+}
+
+impl Plugin for StarfieldPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ClearColor(Color::BLACK));
+        app.add_systems(
+            Update,
+            (
+                spawn_stars.run_if(resource_changed::<GameConfig>),
+                scroll_stars,
+            )
+                .chain(),
+        );
+    }
+}
+
+/// (Re)creates the stars, whenever the density or the size of the visible
+/// area might have changed.
+fn spawn_stars(config: Res<GameConfig>, stars: Query<Entity, With<Star>>, mut cmd: Commands) {
+    for star in &stars {
+        cmd.entity(star).despawn();
+    }
+    let mut rng = StdRng::seed_from_u64(STARFIELD_SEED);
+    let sky_size = playfield_size(&config);
+    let stars_per_layer = (config.star_density * sky_size.x * sky_size.y).round() as u32;
+    for layer in &LAYERS {
+        for _ in 0..stars_per_layer {
+            let position = Vec2::new(
+                rng.gen_range(-0.5..0.5) * sky_size.x,
+                rng.gen_range(-0.5..0.5) * sky_size.y,
+            );
+            // A little variation, so the layers don't look uniform.
+            let brightness = layer.brightness * rng.gen_range(0.7..=1.0);
+            cmd.spawn((
+                Star { speed: layer.speed },
+                Sprite::from_color(
+                    Color::srgb(brightness, brightness, brightness * 1.1),
+                    Vec2::splat(layer.size),
+                ),
+                Transform::from_translation(position.extend(layer.z)),
+            ));
+        }
+    }
+}
+
+fn scroll_stars(
+    time: Res<Time>,
+    state: Res<State<GameStates>>,
+    config: Res<GameConfig>,
+    mut stars: Query<(&Star, &mut Transform)>,
+) {
+    let speed = match state.get() {
+        GameStates::Game => SCROLL_SPEED,
+        _ => SCROLL_SPEED * DRIFT_FACTOR,
+    };
+    let sky_size = playfield_size(&config);
+    for (star, mut transform) in &mut stars {
+        transform.translation.y -= star.speed * speed * time.delta_secs();
+        if transform.translation.y < -sky_size.y / 2.0 {
+            transform.translation.y += sky_size.y;
+        }
+    }
+}
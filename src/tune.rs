@@ -0,0 +1,136 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use bevy::audio::Source;
+use bevy::prelude::*;
+
+const SAMPLE_RATE: u32 = 44_100;
+/// Seconds a note takes to reach its full volume, which avoids clicks.
+const ATTACK: f32 = 0.005;
+/// Peak amplitude of a note, leaving headroom for the other sounds.
+const AMPLITUDE: f32 = 0.5;
+
+/// A melody synthesized while it plays, played like any other sound with an
+/// `AudioPlayer<Tune>`.
+#[derive(Asset, TypePath, Debug, Clone)]
+pub struct Tune {
+    pub notes: Arc<[Note]>,
+    pub waveform: Waveform,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Note {
+    /// In Hz, 0 is a rest.
+    pub frequency: f32,
+    /// In seconds.
+    pub duration: f32,
+}
+
+impl Note {
+    pub const fn new(frequency: f32, duration: f32) -> Self {
+        Note {
+            frequency,
+            duration,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Waveform {
+    /// Hollow and buzzing, like the bass of the arcade machine.
+    Square,
+    /// Soft, close to a flute.
+    Triangle,
+}
+
+impl Waveform {
+    /// The wave at `phase`, which goes from 0 to 1 over one period.
+    fn sample(self, phase: f32) -> f32 {
+        match self {
+            Waveform::Square => {
+                if phase < 0.5 {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+            Waveform::Triangle => 1.0 - 4.0 * (phase - 0.5).abs(),
+        }
+    }
+}
+
+impl Tune {
+    pub fn new(waveform: Waveform, notes: &[Note]) -> Self {
+        Tune {
+            notes: notes.into(),
+            waveform,
+        }
+    }
+}
+
+impl Decodable for Tune {
+    type DecoderItem = f32;
+    type Decoder = TuneDecoder;
+
+    fn decoder(&self) -> TuneDecoder {
+        TuneDecoder {
+            tune: self.clone(),
+            note: 0,
+            sample: 0,
+            phase: 0.0,
+        }
+    }
+}
+
+/// Produces the samples of a `Tune`, one note after the other.
+pub struct TuneDecoder {
+    tune: Tune,
+    note: usize,
+    /// Samples of the current note already produced.
+    sample: u32,
+    phase: f32,
+}
+
+impl Iterator for TuneDecoder {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        loop {
+            let note = self.tune.notes.get(self.note)?;
+            let length = (note.duration * SAMPLE_RATE as f32) as u32;
+            if self.sample >= length {
+                self.note += 1;
+                self.sample = 0;
+                continue;
+            }
+            let t = self.sample as f32 / SAMPLE_RATE as f32;
+            self.sample += 1;
+            if note.frequency == 0.0 {
+                return Some(0.0);
+            }
+            self.phase = (self.phase + note.frequency / SAMPLE_RATE as f32).fract();
+            // Rises quickly, then fades out linearly until the note ends.
+            let envelope = (t / ATTACK).min(1.0) * (1.0 - t / note.duration);
+            return Some(AMPLITUDE * envelope * self.tune.waveform.sample(self.phase));
+        }
+    }
+}
+
+impl Source for TuneDecoder {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        let seconds = self.tune.notes.iter().map(|note| note.duration).sum();
+        Some(Duration::from_secs_f32(seconds))
+    }
+}
@@ -0,0 +1,144 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::ron_asset::{register_ron_asset, RonAsset};
+
+/// Name of the weapon the player starts with, which every weapons file has
+/// to have.
+pub const DEFAULT_WEAPON: &str = "standard";
+
+pub struct WeaponPlugin;
+
+/// A single projectile fired by a weapon.
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct ProjectileSpec {
+    /// Direction in degrees, counter-clockwise from straight up.
+    #[serde(default)]
+    pub angle: f32,
+    /// Factor on the configured bullet speed.
+    #[serde(default = "one")]
+    pub speed: f32,
+    /// Number of targets the projectile flies through, see `Piercing`.
+    #[serde(default)]
+    pub piercing: u32,
+    /// Factor on the size of the bullet sprite and its collider.
+    #[serde(default = "one")]
+    pub scale: f32,
+}
+
+fn one() -> f32 {
+    1.0
+}
+
+impl ProjectileSpec {
+    pub const STRAIGHT: ProjectileSpec = ProjectileSpec {
+        angle: 0.0,
+        speed: 1.0,
+        piercing: 0,
+        scale: 1.0,
+    };
+
+    /// The shot released in the charge fire mode, `charge` going from 0 to 1.
+    pub fn charged(charge: f32) -> ProjectileSpec {
+        ProjectileSpec {
+            angle: 0.0,
+            speed: 0.75,
+            piercing: (charge * 3.0).round() as u32,
+            scale: 1.0 + 2.0 * charge,
+        }
+    }
+
+    pub fn direction(&self) -> Vec2 {
+        Vec2::from_angle(self.angle.to_radians()).rotate(Vec2::Y)
+    }
+}
+
+/// What the player shoots with. Power-ups swap or modify it.
+#[derive(Component, Debug, Clone, Deserialize)]
+pub struct Weapon {
+    /// Seconds between two shots.
+    pub cooldown: f32,
+    /// Projectiles fired at once with each shot.
+    pub projectiles: Vec<ProjectileSpec>,
+}
+
+impl Weapon {
+    /// Divides the cooldown, so the weapon fires `factor` times as often.
+    pub fn with_fire_rate(mut self, factor: f32) -> Self {
+        self.cooldown /= factor;
+        self
+    }
+}
+
+/// The built in weapon, used until the weapons file is loaded.
+impl Default for Weapon {
+    fn default() -> Self {
+        Weapon {
+            cooldown: 0.25,
+            projectiles: vec![ProjectileSpec::STRAIGHT],
+        }
+    }
+}
+
+/// The weapons the player and the power-ups can refer to by name, loaded
+/// from `assets/*.weapons.ron`.
+#[derive(Asset, TypePath, Debug, Clone, Deserialize)]
+pub struct Weapons {
+    pub weapons: BTreeMap<String, Weapon>,
+}
+
+impl Weapons {
+    /// Looks up a weapon, falling back to the default one for unknown names.
+    pub fn get(&self, name: &str) -> Weapon {
+        self.weapons
+            .get(name)
+            .or_else(|| {
+                warn!("unknown weapon {name:?}, using the default one");
+                self.weapons.get(DEFAULT_WEAPON)
+            })
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+impl RonAsset for Weapons {
+    const EXTENSIONS: &'static [&'static str] = &["weapons.ron"];
+
+    fn validate(&self) -> Result<(), String> {
+        if !self.weapons.contains_key(DEFAULT_WEAPON) {
+            return Err(format!("the weapon {DEFAULT_WEAPON:?} is missing"));
+        }
+        for (name, weapon) in &self.weapons {
+            if !(weapon.cooldown.is_finite() && weapon.cooldown > 0.0) {
+                return Err(format!(
+                    "{name}: cooldown has to be positive, but is {}",
+                    weapon.cooldown
+                ));
+            }
+            if weapon.projectiles.is_empty() {
+                return Err(format!("{name}: needs at least one projectile"));
+            }
+            for projectile in &weapon.projectiles {
+                if !(projectile.speed > 0.0 && projectile.scale > 0.0) {
+                    return Err(format!(
+                        "{name}: speed and scale of a projectile have to be positive, but are {} and {}",
+                        projectile.speed, projectile.scale
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Plugin for WeaponPlugin {
+    fn build(&self, app: &mut App) {
+        register_ron_asset::<Weapons>(app);
+    }
+}
+
+/// Time left until the weapon can fire again.
+#[derive(Component, Debug, Default)]
+pub struct WeaponCooldown(pub Timer);